@@ -12,6 +12,8 @@ statement
     | forStatement
     | ifStatement
     | returnStatement
+    | throwStatement
+    | tryStatement
     | continueStatement
     | breakStatement
     | expressionStatement
@@ -69,6 +71,22 @@ returnStatement
     : RETURN expression ';'
     ;
 
+throwStatement
+    : THROW expression ';'
+    ;
+
+tryStatement
+    : TRY block (catchClause finallyClause? | finallyClause)
+    ;
+
+catchClause
+    : CATCH '(' identifier ')' block
+    ;
+
+finallyClause
+    : FINALLY block
+    ;
+
 breakStatement
     : BREAK expression ';'
     ;
@@ -98,7 +116,19 @@ logicalOrExpression
     ;
 
 logicalAndExpression
-    : equalityExpression ('&&' equalityExpression)*
+    : bitwiseOrExpression ('&&' bitwiseOrExpression)*
+    ;
+
+bitwiseOrExpression
+    : bitwiseXorExpression ('|' bitwiseXorExpression)*
+    ;
+
+bitwiseXorExpression
+    : bitwiseAndExpression ('^' bitwiseAndExpression)*
+    ;
+
+bitwiseAndExpression
+    : equalityExpression ('&' equalityExpression)*
     ;
 
 equalityExpression
@@ -106,7 +136,11 @@ equalityExpression
     ;
 
 relationalExpression
-    : additiveExpression (('<' | '>' | '<=' | '>=') additiveExpression)*
+    : shiftExpression (('<' | '>' | '<=' | '>=') shiftExpression)*
+    ;
+
+shiftExpression
+    : additiveExpression (('<<' | '>>') additiveExpression)*
     ;
 
 additiveExpression
@@ -118,7 +152,7 @@ multiplicativeExpression
     ;
 
 unaryExpression
-    : ('!' | '-')* postfixExpression
+    : ('!' | '-' | '~')* postfixExpression
     ;
 
 postfixExpression
@@ -177,6 +211,10 @@ ELSE        : 'else';
 BREAK       : 'break';
 CONTINUE    : 'continue';
 RETURN      : 'return';
+TRY         : 'try';
+CATCH       : 'catch';
+FINALLY     : 'finally';
+THROW       : 'throw';
 
 // Operators
 PLUS        : '+';
@@ -193,10 +231,23 @@ LESS_EQ     : '<=';
 AND         : '&&';
 OR          : '||';
 NOT         : '!';
+BIT_AND     : '&';
+BIT_OR      : '|';
+BIT_XOR     : '^';
+BIT_NOT     : '~';
+SHIFT_LEFT  : '<<';
+SHIFT_RIGHT : '>>';
 SEMICOLON   : ';';
 
 IDENTIFIER  : [a-zA-Z_] [a-zA-Z0-9_]* ;
-NUMBER_LITERAL : [0-9]+ ('.' [0-9]+)? ;
+NUMBER_LITERAL
+    : '0' [xX] HEX_DIGIT+ ('_' HEX_DIGIT+)*
+    | '0' [bB] [01]+ ('_' [01]+)*
+    | '0' [oO] [0-7]+ ('_' [0-7]+)*
+    | DIGITS ('.' DIGITS)? ([eE] [+-]? DIGITS)?
+    ;
+fragment DIGITS : [0-9]+ ('_' [0-9]+)* ;
+fragment HEX_DIGIT : [0-9a-fA-F] ;
 STRING_LITERAL : '"' ~["\r\n]* '"' ;
 WS          : [ \t\r\n]+ -> skip ;
 COMMENT     : '//' ~[\r\n]* -> skip ;
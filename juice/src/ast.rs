@@ -1,7 +1,43 @@
-use std::collections::HashMap;
+use std::ops::Deref;
+
+use crate::juice::symbol::Symbol;
+
+// The first and last source line of a node.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct Span {
+    pub start: usize,
+    pub end: usize,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Spanned<T> {
+    pub node: T,
+    pub span: Span,
+}
+
+impl<T> Deref for Spanned<T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.node
+    }
+}
+
+impl<T> From<T> for Spanned<T> {
+    fn from(node: T) -> Self {
+        Spanned {
+            node,
+            span: Span::default(),
+        }
+    }
+}
+
+pub type Statement = Spanned<StatementKind>;
+// A braced list of statements; its span ends on the closing brace.
+pub type Block = Spanned<Vec<Statement>>;
 
 #[derive(Debug, Clone, PartialEq)]
-pub enum Statement {
+pub enum StatementKind {
     Object {
         name: String,
         type_annotation: Option<Vec<String>>,
@@ -13,54 +49,64 @@ pub enum Statement {
         method_signatures: Vec<MethodSignature>,
     },
     Var {
-        name: String,
+        name: Symbol,
         type_annotation: Option<Vec<String>>,
         initializer: Box<Expression>,
     },
     While {
         condition: Box<Expression>,
-        body: Vec<Statement>,
+        body: Block,
     },
     For {
-        variable: String,
+        variable: Symbol,
         iterator: Box<Expression>,
-        body: Vec<Statement>,
+        body: Block,
     },
     If {
         condition: Box<Expression>,
-        then_branch: Vec<Statement>,
-        else_branch: Option<Vec<Statement>>,
+        then_branch: Block,
+        else_branch: Option<Block>,
     },
     Break,
     Continue,
     Return(Option<Expression>),
+    Throw(Expression),
+    Try {
+        body: Block,
+        catch: Option<(Symbol, Block)>,
+        finally: Option<Block>,
+    },
     Expression(Expression),
 }
 
 #[derive(Debug, Clone, PartialEq)]
 pub struct MethodDeclaration {
     pub signature: MethodSignature,
-    pub body: Vec<Statement>,
+    pub body: Block,
 }
 
 #[derive(Debug, Clone, PartialEq)]
 pub struct MethodSignature {
-    pub name: String,
+    pub name: Symbol,
     pub params: Vec<Parameter>,
     pub return_type: Option<Vec<String>>,
+    pub span: Span,
 }
 
 #[derive(Debug, Clone, PartialEq)]
 pub struct Parameter {
-    pub name: String,
+    pub name: Symbol,
     pub type_annotation: Option<Vec<String>>,
 }
 
 #[derive(Debug, Clone, PartialEq)]
 pub enum Expression {
+    // Bracketed lists span from their opening to their closing bracket, and so does each of
+    // their entries, so the formatter can keep comments beside them.
     Call {
         callee: Box<Expression>,
-        arguments: Vec<Expression>,
+        arguments: Vec<Spanned<Expression>>,
+        span: Span,
     },
     Binary {
         left: Box<Expression>,
@@ -85,15 +131,19 @@ pub enum Expression {
     },
     ObjectConstruction {
         type_name: Option<String>,
-        fields: HashMap<String, Expression>,
+        fields: Vec<(Symbol, Spanned<Expression>)>,
+        span: Span,
     },
     ArrayConstruction {
-        elements: Vec<Expression>,
+        elements: Vec<Spanned<Expression>>,
+        span: Span,
     },
-    Identifier(String),
-    CallableIdentifier(String),
-    StringLiteral(String),
-    NumberLiteral(String),
+    Identifier(Symbol),
+    CallableIdentifier(Symbol),
+    StringLiteral(Symbol),
+    // Number literals keep their spelling, e.g. `0xFF_00`, so the formatter can print it back.
+    IntegerLiteral(i64, String),
+    NumberLiteral(f64, String),
     BoolLiteral(bool),
     Null,
 }
@@ -113,10 +163,16 @@ pub enum BinaryOp {
     GreaterEqual,
     And,
     Or,
+    BitAnd,
+    BitOr,
+    BitXor,
+    ShiftLeft,
+    ShiftRight,
 }
 
 #[derive(Debug, Clone, PartialEq)]
 pub enum UnaryOp {
     Negate,
     Not,
+    BitNot,
 }
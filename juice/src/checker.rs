@@ -0,0 +1,300 @@
+use std::collections::HashSet;
+
+use crate::ast::{Expression, MethodDeclaration, Statement, StatementKind};
+use crate::juice::error::{Error, ErrorKind};
+
+/// Finds mistakes that would only surface at runtime, without executing anything.
+pub struct Checker {
+    loop_depth: usize,
+    in_method: bool,
+    // The line of the statement being checked, for problems found in its expressions.
+    line: usize,
+    errors: Vec<Error>,
+}
+
+impl Checker {
+    pub fn new() -> Self {
+        Checker {
+            loop_depth: 0,
+            in_method: false,
+            line: 0,
+            errors: Vec::new(),
+        }
+    }
+
+    pub fn check(mut self, statements: &[Statement]) -> Vec<Error> {
+        self.statements(statements);
+        self.errors
+    }
+
+    fn report(&mut self, line: usize, kind: ErrorKind, message: String) {
+        self.errors
+            .push(Error::new(kind, format!("{} at line {}", message, line)));
+    }
+
+    fn statements(&mut self, statements: &[Statement]) {
+        for statement in statements {
+            self.statement(statement);
+        }
+    }
+
+    fn statement(&mut self, statement: &Statement) {
+        self.line = statement.span.start;
+        match &statement.node {
+            StatementKind::Object { name, methods, .. } => self.object(name, methods),
+            StatementKind::Trait {
+                name,
+                method_signatures,
+                ..
+            } => {
+                let mut seen = HashSet::new();
+                for signature in method_signatures {
+                    if !seen.insert(&signature.name) {
+                        self.report(
+                            signature.span.start,
+                            ErrorKind::Syntax,
+                            format!("Duplicate method {} in trait {}", signature.name, name),
+                        );
+                    }
+                }
+            }
+            StatementKind::Var { initializer, .. } => self.expression(initializer),
+            StatementKind::While { condition, body } => {
+                self.expression(condition);
+                self.loop_body(body);
+            }
+            StatementKind::For { iterator, body, .. } => {
+                self.expression(iterator);
+                self.loop_body(body);
+            }
+            StatementKind::If {
+                condition,
+                then_branch,
+                else_branch,
+            } => {
+                self.expression(condition);
+                self.statements(then_branch);
+                if let Some(else_branch) = else_branch {
+                    self.statements(else_branch);
+                }
+            }
+            StatementKind::Break if self.loop_depth == 0 => self.report(
+                self.line,
+                ErrorKind::Control,
+                "Break statement outside of loop".to_string(),
+            ),
+            StatementKind::Continue if self.loop_depth == 0 => self.report(
+                self.line,
+                ErrorKind::Control,
+                "Continue statement outside of loop".to_string(),
+            ),
+            StatementKind::Break | StatementKind::Continue => {}
+            StatementKind::Return(value) => {
+                if !self.in_method {
+                    self.report(
+                        self.line,
+                        ErrorKind::Control,
+                        "Return statement outside of method".to_string(),
+                    );
+                }
+                if let Some(value) = value {
+                    self.expression(value);
+                }
+            }
+            StatementKind::Throw(value) | StatementKind::Expression(value) => {
+                self.expression(value)
+            }
+            StatementKind::Try {
+                body,
+                catch,
+                finally,
+            } => {
+                self.statements(body);
+                if let Some((_, catch_body)) = catch {
+                    self.statements(catch_body);
+                }
+                if let Some(finally) = finally {
+                    self.statements(finally);
+                }
+            }
+        }
+    }
+
+    fn object(&mut self, name: &str, methods: &[MethodDeclaration]) {
+        let mut seen = HashSet::new();
+        for method in methods {
+            let signature = &method.signature;
+            if !seen.insert(&signature.name) {
+                self.report(
+                    signature.span.start,
+                    ErrorKind::Syntax,
+                    format!("Duplicate method {} in object {}", signature.name, name),
+                );
+            }
+
+            let mut params = HashSet::new();
+            for param in &signature.params {
+                if !params.insert(&param.name) {
+                    self.report(
+                        signature.span.start,
+                        ErrorKind::Syntax,
+                        format!(
+                            "Duplicate parameter {} in method {}.{}",
+                            param.name, name, signature.name
+                        ),
+                    );
+                }
+            }
+
+            // A method body starts fresh: loops around the declaration do not apply inside it.
+            let (loop_depth, in_method) = (self.loop_depth, self.in_method);
+            self.loop_depth = 0;
+            self.in_method = true;
+            self.statements(&method.body);
+            self.loop_depth = loop_depth;
+            self.in_method = in_method;
+        }
+    }
+
+    fn loop_body(&mut self, body: &[Statement]) {
+        self.loop_depth += 1;
+        self.statements(body);
+        self.loop_depth -= 1;
+    }
+
+    fn expression(&mut self, expression: &Expression) {
+        match expression {
+            Expression::Call {
+                callee, arguments, ..
+            } => {
+                self.expression(callee);
+                for argument in arguments {
+                    self.expression(argument);
+                }
+            }
+            Expression::Binary { left, right, .. } => {
+                self.expression(left);
+                self.expression(right);
+            }
+            Expression::Unary { operand, .. } => self.expression(operand),
+            Expression::DotAccess { object, .. } => self.expression(object),
+            Expression::IndexAccess { object, index } => {
+                self.expression(object);
+                self.expression(index);
+            }
+            Expression::Assignment { target, value } => {
+                if !matches!(
+                    **target,
+                    Expression::Identifier(_)
+                        | Expression::DotAccess { .. }
+                        | Expression::IndexAccess { .. }
+                ) {
+                    self.report(
+                        self.line,
+                        ErrorKind::Syntax,
+                        "Invalid assignment target".to_string(),
+                    );
+                }
+                self.expression(target);
+                self.expression(value);
+            }
+            Expression::ObjectConstruction { fields, .. } => {
+                for (_, value) in fields {
+                    self.expression(value);
+                }
+            }
+            Expression::ArrayConstruction { elements, .. } => {
+                for element in elements {
+                    self.expression(element);
+                }
+            }
+            Expression::Identifier(_)
+            | Expression::CallableIdentifier(_)
+            | Expression::StringLiteral(_)
+            | Expression::IntegerLiteral(..)
+            | Expression::NumberLiteral(..)
+            | Expression::BoolLiteral(_)
+            | Expression::Null => {}
+        }
+    }
+}
+
+impl Default for Checker {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{lexer::Lexer, parser::Parser};
+
+    fn check(source: &str) -> Vec<String> {
+        let statements = Parser::new(Lexer::new(source).lex()).parse().unwrap();
+        Checker::new()
+            .check(&statements)
+            .into_iter()
+            .map(|error| error.message)
+            .collect::<Vec<_>>()
+    }
+
+    #[test]
+    fn test_valid_program() {
+        let source = r#"
+            object List {
+                find(items, wanted) {
+                    for (var item in items) {
+                        if (item == wanted) { return item; }
+                        if (item == null) { continue; }
+                    }
+                    while (true) { break; }
+                    return null;
+                }
+            }
+            var list = List {};
+            list.find([1, 2], 2);
+        "#;
+        assert_eq!(check(source), Vec::<String>::new());
+    }
+
+    #[test]
+    fn test_reports_misplaced_control_flow() {
+        let source = r#"
+            break;
+            while (true) {
+                object Inner { run() { continue; } }
+            }
+            try { return 1; } finally { }
+        "#;
+        assert_eq!(
+            check(source),
+            [
+                "Break statement outside of loop at line 2",
+                "Continue statement outside of loop at line 4",
+                "Return statement outside of method at line 6",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_reports_duplicates() {
+        let source = r#"
+            object Point {
+                move(x, x) { }
+                move(y) { }
+            }
+            trait Shape { area(); area(); }
+            1 = 2;
+        "#;
+        assert_eq!(
+            check(source),
+            [
+                "Duplicate parameter x in method Point.move at line 3",
+                "Duplicate method move in object Point at line 4",
+                "Duplicate method area in trait Shape at line 6",
+                "Invalid assignment target at line 7",
+            ]
+        );
+    }
+}
@@ -0,0 +1,915 @@
+use crate::ast::{
+    BinaryOp, Block, Expression, MethodSignature, Parameter, Span, Spanned, Statement,
+    StatementKind, UnaryOp,
+};
+use crate::juice::error::Error;
+use crate::lexer::Lexer;
+use crate::parser::Parser;
+use crate::token::Comment;
+
+const INDENT: &str = "    ";
+// Literals longer than this on one line are split with one entry per line.
+const MAX_INLINE_WIDTH: usize = 60;
+
+// Binding strength, loosest first, mirroring the parser's precedence climbing.
+const ASSIGNMENT: u8 = 1;
+const UNARY: u8 = 12;
+const POSTFIX: u8 = 13;
+
+/// Formats source code, keeping its comments and single blank lines.
+pub fn format_source(source: &str) -> Result<String, Error> {
+    let mut lexer = Lexer::new(source);
+    let tokens = lexer.lex();
+    let statements = Parser::new(tokens).parse()?;
+    Ok(Formatter::new()
+        .with_comments(lexer.comments())
+        .format(&statements))
+}
+
+/// Prints canonical source for a parsed program.
+pub struct Formatter {
+    output: String,
+    indent: usize,
+    comments: Vec<Comment>,
+    next_comment: usize,
+    // The source line of the code printed last, and the last source line printed at all.
+    code_line: usize,
+    last_line: usize,
+}
+
+impl Formatter {
+    pub fn new() -> Self {
+        Formatter {
+            output: String::new(),
+            indent: 0,
+            comments: Vec::new(),
+            next_comment: 0,
+            code_line: 0,
+            last_line: 0,
+        }
+    }
+
+    /// Puts `comments` back into the output, next to the statements whose spans surround them.
+    pub fn with_comments(mut self, comments: &[Comment]) -> Self {
+        self.comments = comments.to_vec();
+        self
+    }
+
+    pub fn format(mut self, statements: &[Statement]) -> String {
+        self.statements(statements);
+        self.comments_before(usize::MAX);
+        self.output
+    }
+
+    fn line(&mut self, text: &str) {
+        for _ in 0..self.indent {
+            self.output.push_str(INDENT);
+        }
+        self.output.push_str(text);
+        self.output.push('\n');
+    }
+
+    fn blank_line(&mut self) {
+        if !self.output.is_empty()
+            && !self.output.ends_with("\n\n")
+            && !self.output.ends_with("{\n")
+        {
+            self.output.push('\n');
+        }
+    }
+
+    // Keeps one blank line wherever the source had at least one.
+    fn gap(&mut self, line: usize) {
+        if self.last_line > 0 && line > self.last_line + 1 {
+            self.blank_line();
+        }
+    }
+
+    fn visit(&mut self, line: usize) {
+        self.code_line = line;
+        self.last_line = self.last_line.max(line);
+    }
+
+    // Takes the comments written before `line` that are not printed yet.
+    fn take_comments(&mut self, line: usize) -> Vec<Comment> {
+        let first = self.next_comment;
+        while self
+            .comments
+            .get(self.next_comment)
+            .is_some_and(|comment| comment.line < line)
+        {
+            self.next_comment += 1;
+        }
+        self.comments[first..self.next_comment].to_vec()
+    }
+
+    fn comments_before(&mut self, line: usize) {
+        for comment in self.take_comments(line) {
+            if comment.line == self.code_line && self.output.ends_with('\n') {
+                // A comment after code stays at the end of that code's line.
+                self.output.pop();
+                self.output.push(' ');
+                self.output.push_str(&comment.text);
+                self.output.push('\n');
+            } else {
+                self.gap(comment.line);
+                self.line(&comment.text);
+            }
+            self.last_line = self.last_line.max(comment.line);
+        }
+    }
+
+    fn has_comments_before(&self, line: usize) -> bool {
+        self.comments
+            .get(self.next_comment)
+            .is_some_and(|comment| comment.line < line)
+    }
+
+    // Whether a comment sits between the opening bracket of `span` and its closing line.
+    fn has_comments_within(&self, span: Span) -> bool {
+        self.comments[self.next_comment..]
+            .iter()
+            .find(|comment| comment.line >= span.start)
+            .is_some_and(|comment| comment.line < span.end)
+    }
+
+    // Starts an item that begins at `line`, after the comments and blank lines before it.
+    fn start(&mut self, line: usize, separated: bool) {
+        if separated {
+            self.blank_line();
+        }
+        self.comments_before(line);
+        self.gap(line);
+        self.visit(line);
+    }
+
+    fn statements(&mut self, statements: &[Statement]) {
+        for (i, statement) in statements.iter().enumerate() {
+            let Span { start, end } = statement.span;
+            // Declarations are set apart from whatever surrounds them.
+            let separated =
+                i > 0 && (is_declaration(statement) || is_declaration(&statements[i - 1]));
+            self.start(start, separated);
+            self.statement(statement);
+            self.visit(end);
+        }
+    }
+
+    fn block(&mut self, header: &str, body: &Block) {
+        for _ in 0..self.indent {
+            self.output.push_str(INDENT);
+        }
+        self.output.push_str(header);
+        self.body(body);
+    }
+
+    // Continues the previous block on its closing line, as in `} else {`.
+    fn continue_block(&mut self, header: &str, body: &Block) {
+        if self.has_comments_before(body.span.start) {
+            // Comments after the closing brace stay there, so the next part starts a new line.
+            self.comments_before(body.span.start);
+            self.block(header, body);
+            return;
+        }
+        self.output.pop();
+        self.output.push(' ');
+        self.output.push_str(header);
+        self.body(body);
+    }
+
+    fn body(&mut self, body: &Block) {
+        self.output.push_str(" {\n");
+        self.indent += 1;
+        self.statements(body);
+        self.close(body.span.end);
+    }
+
+    // Ends the innermost block at the closing brace on `line`, keeping the comments before it.
+    fn close(&mut self, line: usize) {
+        if self.output.ends_with("{\n") && !self.has_comments_before(line) {
+            self.output.pop();
+            self.output.push_str("}\n");
+            self.indent -= 1;
+        } else {
+            self.comments_before(line);
+            self.indent -= 1;
+            self.line("}");
+        }
+        self.visit(line);
+    }
+
+    fn statement(&mut self, statement: &Statement) {
+        match &statement.node {
+            StatementKind::Object {
+                name,
+                type_annotation,
+                methods,
+            } => {
+                let header = format!("object {}{}", name, traits(type_annotation));
+                self.line(&format!("{} {{", header));
+                self.indent += 1;
+                for (i, method) in methods.iter().enumerate() {
+                    self.start(method.signature.span.start, i > 0);
+                    self.block(&self.signature(&method.signature), &method.body);
+                }
+                self.close(statement.span.end);
+            }
+            StatementKind::Trait {
+                name,
+                type_annotation,
+                method_signatures,
+            } => {
+                let header = format!("trait {}{}", name, traits(type_annotation));
+                self.line(&format!("{} {{", header));
+                self.indent += 1;
+                for signature in method_signatures {
+                    self.start(signature.span.start, false);
+                    self.line(&format!("{};", self.signature(signature)));
+                }
+                self.close(statement.span.end);
+            }
+            StatementKind::Var {
+                name,
+                type_annotation,
+                initializer,
+            } => {
+                let initializer = self.expression(initializer, ASSIGNMENT);
+                let text = format!(
+                    "var {}{} = {};",
+                    name,
+                    annotation(type_annotation),
+                    initializer
+                );
+                self.simple(statement, &text);
+            }
+            StatementKind::While { condition, body } => {
+                let condition = self.expression(condition, ASSIGNMENT);
+                self.block(&format!("while ({})", condition), body);
+            }
+            StatementKind::For {
+                variable,
+                iterator,
+                body,
+            } => {
+                let iterator = self.expression(iterator, ASSIGNMENT);
+                self.block(&format!("for (var {} in {})", variable, iterator), body);
+            }
+            StatementKind::If {
+                condition,
+                then_branch,
+                else_branch,
+            } => {
+                let condition = self.expression(condition, ASSIGNMENT);
+                self.block(&format!("if ({})", condition), then_branch);
+                if let Some(else_branch) = else_branch {
+                    self.continue_block("else", else_branch);
+                }
+            }
+            StatementKind::Break => self.simple(statement, "break;"),
+            StatementKind::Continue => self.simple(statement, "continue;"),
+            StatementKind::Return(None) => self.simple(statement, "return;"),
+            StatementKind::Return(Some(value)) => {
+                let value = self.expression(value, ASSIGNMENT);
+                self.simple(statement, &format!("return {};", value));
+            }
+            StatementKind::Throw(value) => {
+                let value = self.expression(value, ASSIGNMENT);
+                self.simple(statement, &format!("throw {};", value));
+            }
+            StatementKind::Try {
+                body,
+                catch,
+                finally,
+            } => {
+                self.block("try", body);
+                if let Some((variable, catch_body)) = catch {
+                    self.continue_block(&format!("catch ({})", variable), catch_body);
+                }
+                if let Some(finally) = finally {
+                    self.continue_block("finally", finally);
+                }
+            }
+            StatementKind::Expression(expression) => {
+                let expression = self.expression(expression, ASSIGNMENT);
+                self.simple(statement, &format!("{};", expression));
+            }
+        }
+    }
+
+    // Prints a statement without a body. Comments inside it that no list kept beside its
+    // entries move above it.
+    fn simple(&mut self, statement: &Statement, text: &str) {
+        for comment in self.take_comments(statement.span.end) {
+            self.line(&comment.text);
+        }
+        self.line(text);
+    }
+
+    fn signature(&self, signature: &MethodSignature) -> String {
+        let params = signature
+            .params
+            .iter()
+            .map(parameter)
+            .collect::<Vec<_>>()
+            .join(", ");
+        format!(
+            "{}({}){}",
+            signature.name,
+            params,
+            annotation(&signature.return_type)
+        )
+    }
+
+    fn expression(&mut self, expression: &Expression, min_precedence: u8) -> String {
+        let precedence = precedence(expression);
+        let text = match expression {
+            Expression::Call {
+                callee,
+                arguments,
+                span,
+            } => {
+                let callee = self.expression(callee, POSTFIX);
+                if self.has_comments_within(*span) {
+                    let arguments = arguments
+                        .iter()
+                        .map(|argument| (String::new(), argument))
+                        .collect::<Vec<_>>();
+                    // The parser takes no trailing comma after the last argument.
+                    self.commented_list(&format!("{}(", callee), ")", &arguments, *span, false)
+                } else {
+                    let arguments = arguments
+                        .iter()
+                        .map(|argument| self.expression(argument, ASSIGNMENT))
+                        .collect::<Vec<_>>()
+                        .join(", ");
+                    format!("{}({})", callee, arguments)
+                }
+            }
+            Expression::Binary {
+                left,
+                operator,
+                right,
+            } => format!(
+                "{} {} {}",
+                self.expression(left, precedence),
+                binary_operator(operator),
+                // Operators associate to the left, so an equal right operand needs parentheses.
+                self.expression(right, precedence + 1)
+            ),
+            Expression::Unary { operator, operand } => {
+                let operator = match operator {
+                    UnaryOp::Negate => "-",
+                    UnaryOp::Not => "!",
+                    UnaryOp::BitNot => "~",
+                };
+                let operand = self.expression(operand, UNARY);
+                // Keeps `- -x` from reading as a decrement.
+                let space = if operator == "-" && operand.starts_with('-') {
+                    " "
+                } else {
+                    ""
+                };
+                format!("{}{}{}", operator, space, operand)
+            }
+            Expression::DotAccess { object, identifier } => format!(
+                "{}.{}",
+                self.expression(object, POSTFIX),
+                self.expression(identifier, POSTFIX)
+            ),
+            Expression::IndexAccess { object, index } => format!(
+                "{}[{}]",
+                self.expression(object, POSTFIX),
+                self.expression(index, ASSIGNMENT)
+            ),
+            Expression::Assignment { target, value } => format!(
+                "{} = {}",
+                self.expression(target, ASSIGNMENT + 1),
+                self.expression(value, ASSIGNMENT)
+            ),
+            Expression::ObjectConstruction {
+                type_name,
+                fields,
+                span,
+            } => {
+                let prefix = match type_name {
+                    Some(type_name) => format!("{} ", type_name),
+                    None => String::new(),
+                };
+                if self.has_comments_within(*span) {
+                    let fields = fields
+                        .iter()
+                        .map(|(name, value)| (format!("{} = ", name), value))
+                        .collect::<Vec<_>>();
+                    self.commented_list(&format!("{}{{", prefix), "}", &fields, *span, true)
+                } else if fields.is_empty() {
+                    format!("{}{{}}", prefix)
+                } else {
+                    let fields = fields
+                        .iter()
+                        .map(|(name, value)| {
+                            format!("{} = {}", name, self.nested_expression(value))
+                        })
+                        .collect::<Vec<_>>();
+                    let nested = self.has_nested_object(expression);
+                    self.literal(&prefix, "{", "}", &fields, nested, true)
+                }
+            }
+            Expression::ArrayConstruction { elements, span } => {
+                if self.has_comments_within(*span) {
+                    let elements = elements
+                        .iter()
+                        .map(|element| (String::new(), element))
+                        .collect::<Vec<_>>();
+                    self.commented_list("[", "]", &elements, *span, true)
+                } else {
+                    let elements = elements
+                        .iter()
+                        .map(|element| self.nested_expression(element))
+                        .collect::<Vec<_>>();
+                    let nested = self.has_nested_object(expression);
+                    self.literal("", "[", "]", &elements, nested, false)
+                }
+            }
+            Expression::Identifier(name) | Expression::CallableIdentifier(name) => name.to_string(),
+            Expression::StringLiteral(string) => format!("\"{}\"", string),
+            Expression::IntegerLiteral(_, lexeme) | Expression::NumberLiteral(_, lexeme) => {
+                lexeme.clone()
+            }
+            Expression::BoolLiteral(b) => b.to_string(),
+            Expression::Null => "null".to_string(),
+        };
+
+        if precedence < min_precedence {
+            format!("({})", text)
+        } else {
+            text
+        }
+    }
+
+    // Entries of a multi-line literal are printed one level deeper than the literal itself.
+    fn nested_expression(&mut self, expression: &Expression) -> String {
+        self.indent += 1;
+        let text = self.expression(expression, ASSIGNMENT);
+        self.indent -= 1;
+        text
+    }
+
+    fn has_nested_object(&self, expression: &Expression) -> bool {
+        let children: Vec<&Expression> = match expression {
+            Expression::ObjectConstruction { fields, .. } => {
+                fields.iter().map(|(_, value)| &value.node).collect()
+            }
+            Expression::ArrayConstruction { elements, .. } => {
+                elements.iter().map(|element| &element.node).collect()
+            }
+            _ => return false,
+        };
+        children.into_iter().any(|child| match child {
+            Expression::ObjectConstruction { fields, .. } => !fields.is_empty(),
+            Expression::ArrayConstruction { .. } => self.has_nested_object(child),
+            _ => false,
+        })
+    }
+
+    fn literal(
+        &self,
+        prefix: &str,
+        open: &str,
+        close: &str,
+        entries: &[String],
+        nested: bool,
+        padded: bool,
+    ) -> String {
+        let pad = if padded { " " } else { "" };
+        let inline = format!(
+            "{}{}{}{}{}{}",
+            prefix,
+            open,
+            pad,
+            entries.join(", "),
+            pad,
+            close
+        );
+        if !nested && inline.len() <= MAX_INLINE_WIDTH && !inline.contains('\n') {
+            return inline;
+        }
+
+        let indent = INDENT.repeat(self.indent);
+        let mut text = format!("{}{}\n", prefix, open);
+        for entry in entries {
+            text.push_str(&format!("{}{}{},\n", indent, INDENT, entry));
+        }
+        text.push_str(&format!("{}{}", indent, close));
+        text
+    }
+
+    // Lays out a list with comments inside one entry per line, each comment above or beside
+    // the entry it was written at. Entries are `(prefix, value)` pairs, as in `x = ` and `1`.
+    fn commented_list(
+        &mut self,
+        open: &str,
+        close: &str,
+        entries: &[(String, &Spanned<Expression>)],
+        span: Span,
+        trailing_comma: bool,
+    ) -> String {
+        let indent = INDENT.repeat(self.indent);
+        let mut text = open.to_string();
+        for (i, (prefix, entry)) in entries.iter().enumerate() {
+            for comment in self.take_comments(entry.span.start) {
+                if comment.line == span.start {
+                    text.push_str(&format!(" {}", comment.text));
+                } else {
+                    text.push_str(&format!("\n{}{}{}", indent, INDENT, comment.text));
+                }
+            }
+            let value = self.nested_expression(entry);
+            // Comments inside the entry that no nested list kept move above it.
+            for comment in self.take_comments(entry.span.end) {
+                text.push_str(&format!("\n{}{}{}", indent, INDENT, comment.text));
+            }
+            let comma = if i + 1 < entries.len() || trailing_comma {
+                ","
+            } else {
+                ""
+            };
+            text.push_str(&format!(
+                "\n{}{}{}{}{}",
+                indent, INDENT, prefix, value, comma
+            ));
+
+            // A comment after the last entry on a line stays beside it.
+            let next = entries
+                .get(i + 1)
+                .map_or(span.end, |(_, next)| next.span.start);
+            if next > entry.span.end {
+                for comment in self.take_comments(entry.span.end + 1) {
+                    text.push_str(&format!(" {}", comment.text));
+                }
+            }
+        }
+        for comment in self.take_comments(span.end) {
+            text.push_str(&format!("\n{}{}{}", indent, INDENT, comment.text));
+        }
+        text.push_str(&format!("\n{}{}", indent, close));
+        text
+    }
+}
+
+impl Default for Formatter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn is_declaration(statement: &Statement) -> bool {
+    matches!(
+        statement.node,
+        StatementKind::Object { .. } | StatementKind::Trait { .. }
+    )
+}
+
+fn annotation(types: &Option<Vec<String>>) -> String {
+    match types {
+        Some(types) => format!(": {}", types.join(" + ")),
+        None => String::new(),
+    }
+}
+
+// Declarations put spaces around the colon, as in `object Text : Renderable`.
+fn traits(types: &Option<Vec<String>>) -> String {
+    match types {
+        Some(types) => format!(" : {}", types.join(" + ")),
+        None => String::new(),
+    }
+}
+
+fn parameter(parameter: &Parameter) -> String {
+    format!(
+        "{}{}",
+        parameter.name,
+        annotation(&parameter.type_annotation)
+    )
+}
+
+fn precedence(expression: &Expression) -> u8 {
+    match expression {
+        Expression::Assignment { .. } => ASSIGNMENT,
+        Expression::Binary { operator, .. } => match operator {
+            BinaryOp::Or => 2,
+            BinaryOp::And => 3,
+            BinaryOp::BitOr => 4,
+            BinaryOp::BitXor => 5,
+            BinaryOp::BitAnd => 6,
+            BinaryOp::Equal | BinaryOp::NotEqual => 7,
+            BinaryOp::Less | BinaryOp::LessEqual | BinaryOp::Greater | BinaryOp::GreaterEqual => 8,
+            BinaryOp::ShiftLeft | BinaryOp::ShiftRight => 9,
+            BinaryOp::Add | BinaryOp::Subtract => 10,
+            BinaryOp::Multiply | BinaryOp::Divide | BinaryOp::Modulo => 11,
+        },
+        Expression::Unary { .. } => UNARY,
+        // A negative literal can only come from folding, and reads like a unary minus.
+        Expression::IntegerLiteral(_, lexeme) | Expression::NumberLiteral(_, lexeme)
+            if lexeme.starts_with('-') =>
+        {
+            UNARY
+        }
+        _ => POSTFIX,
+    }
+}
+
+fn binary_operator(operator: &BinaryOp) -> &'static str {
+    match operator {
+        BinaryOp::Add => "+",
+        BinaryOp::Subtract => "-",
+        BinaryOp::Multiply => "*",
+        BinaryOp::Divide => "/",
+        BinaryOp::Modulo => "%",
+        BinaryOp::Equal => "==",
+        BinaryOp::NotEqual => "!=",
+        BinaryOp::Less => "<",
+        BinaryOp::LessEqual => "<=",
+        BinaryOp::Greater => ">",
+        BinaryOp::GreaterEqual => ">=",
+        BinaryOp::And => "&&",
+        BinaryOp::Or => "||",
+        BinaryOp::BitAnd => "&",
+        BinaryOp::BitOr => "|",
+        BinaryOp::BitXor => "^",
+        BinaryOp::ShiftLeft => "<<",
+        BinaryOp::ShiftRight => ">>",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{lexer::Lexer, parser::Parser};
+
+    fn format(source: &str) -> String {
+        let statements = Parser::new(Lexer::new(source).lex()).parse().unwrap();
+        Formatter::new().format(&statements)
+    }
+
+    // Formatting moves code between lines, so trees are compared without their spans.
+    fn strip_block(block: &mut Block) {
+        block.span = Span::default();
+        for statement in &mut block.node {
+            strip_statement(statement);
+        }
+    }
+
+    fn strip_statement(statement: &mut Statement) {
+        statement.span = Span::default();
+        match &mut statement.node {
+            StatementKind::Object { methods, .. } => {
+                for method in methods {
+                    method.signature.span = Span::default();
+                    strip_block(&mut method.body);
+                }
+            }
+            StatementKind::Trait {
+                method_signatures, ..
+            } => {
+                for signature in method_signatures {
+                    signature.span = Span::default();
+                }
+            }
+            StatementKind::Var { initializer, .. } => strip_expression(initializer),
+            StatementKind::While { condition, body } => {
+                strip_expression(condition);
+                strip_block(body);
+            }
+            StatementKind::For { iterator, body, .. } => {
+                strip_expression(iterator);
+                strip_block(body);
+            }
+            StatementKind::If {
+                condition,
+                then_branch,
+                else_branch,
+            } => {
+                strip_expression(condition);
+                strip_block(then_branch);
+                if let Some(else_branch) = else_branch {
+                    strip_block(else_branch);
+                }
+            }
+            StatementKind::Return(Some(value))
+            | StatementKind::Throw(value)
+            | StatementKind::Expression(value) => strip_expression(value),
+            StatementKind::Try {
+                body,
+                catch,
+                finally,
+            } => {
+                strip_block(body);
+                if let Some((_, catch_body)) = catch {
+                    strip_block(catch_body);
+                }
+                if let Some(finally) = finally {
+                    strip_block(finally);
+                }
+            }
+            StatementKind::Return(None) | StatementKind::Break | StatementKind::Continue => {}
+        }
+    }
+
+    fn strip_expression(expression: &mut Expression) {
+        match expression {
+            Expression::Call {
+                callee,
+                arguments,
+                span,
+            } => {
+                *span = Span::default();
+                strip_expression(callee);
+                for argument in arguments {
+                    argument.span = Span::default();
+                    strip_expression(&mut argument.node);
+                }
+            }
+            Expression::ObjectConstruction { fields, span, .. } => {
+                *span = Span::default();
+                for (_, value) in fields {
+                    value.span = Span::default();
+                    strip_expression(&mut value.node);
+                }
+            }
+            Expression::ArrayConstruction { elements, span } => {
+                *span = Span::default();
+                for element in elements {
+                    element.span = Span::default();
+                    strip_expression(&mut element.node);
+                }
+            }
+            Expression::Binary { left, right, .. } => {
+                strip_expression(left);
+                strip_expression(right);
+            }
+            Expression::Unary { operand, .. } => strip_expression(operand),
+            Expression::DotAccess { object, .. } => strip_expression(object),
+            Expression::IndexAccess { object, index } => {
+                strip_expression(object);
+                strip_expression(index);
+            }
+            Expression::Assignment { target, value } => {
+                strip_expression(target);
+                strip_expression(value);
+            }
+            _ => {}
+        }
+    }
+
+    #[test]
+    fn test_keeps_required_parentheses() {
+        assert_eq!(
+            format("x = (a + b) * c - (d - e) - f;"),
+            "x = (a + b) * c - (d - e) - f;\n"
+        );
+        assert_eq!(
+            format("x = -(a + 1) << (b | c) == ((d));"),
+            "x = -(a + 1) << (b | c) == d;\n"
+        );
+        assert_eq!(format("a = b = (c = d);"), "a = b = c = d;\n");
+        assert_eq!(format("x = -(-1);"), "x = - -1;\n");
+    }
+
+    #[test]
+    fn test_literals() {
+        assert_eq!(
+            format("var p = Point{x=1,y=2.50,};"),
+            "var p = Point { x = 1, y = 2.50 };\n"
+        );
+        assert_eq!(
+            format("var n = [0xFF_00,0b1010,0o17,1_000_000,1e-6,2.5E+3];"),
+            "var n = [0xFF_00, 0b1010, 0o17, 1_000_000, 1e-6, 2.5E+3];\n"
+        );
+        assert_eq!(
+            format("var e = [Text {text = \"hi\", at = {x = 0}}, []];"),
+            "var e = [\n    Text {\n        text = \"hi\",\n        at = { x = 0 },\n    },\n    [],\n];\n"
+        );
+    }
+
+    #[test]
+    fn test_statements() {
+        let source = "object A : B + C { init() { } run(x: number): void { if (x) { return; } else { throw x; } } } var a = A {}; while (true) { break; }";
+        let expected = "\
+object A : B + C {
+    init() {}
+
+    run(x: number): void {
+        if (x) {
+            return;
+        } else {
+            throw x;
+        }
+    }
+}
+
+var a = A {};
+while (true) {
+    break;
+}
+";
+        assert_eq!(format(source), expected);
+        assert_eq!(format(expected), expected);
+    }
+
+    #[test]
+    fn test_keeps_comments() {
+        let source = "\
+// about A
+object A : B { // trailing
+  init() {
+    // only a comment
+  }
+
+
+  run(x) {
+    var list = [
+      1, // one
+      2,
+    ];
+    if (x) { return 1; } // after if
+    else { return 2; }
+    print(\"a\", // first
+      \"b\");
+    var p = Point { // point
+      x = 1, // x coord
+      // y coord
+      y = 2 };
+    // end of run
+  }
+}
+var x = 1; // x
+// eof
+";
+        let expected = "\
+// about A
+object A : B { // trailing
+    init() {
+        // only a comment
+    }
+
+    run(x) {
+        var list = [
+            1, // one
+            2,
+        ];
+        if (x) {
+            return 1;
+        } // after if
+        else {
+            return 2;
+        }
+        print(
+            \"a\", // first
+            \"b\"
+        );
+        var p = Point { // point
+            x = 1, // x coord
+            // y coord
+            y = 2,
+        };
+        // end of run
+    }
+}
+
+var x = 1; // x
+// eof
+";
+        assert_eq!(format_source(source).unwrap(), expected);
+        assert_eq!(format_source(expected).unwrap(), expected);
+    }
+
+    #[test]
+    fn test_examples_are_stable() {
+        let examples = concat!(env!("CARGO_MANIFEST_DIR"), "/example");
+        for entry in std::fs::read_dir(examples).unwrap() {
+            let path = entry.unwrap().path();
+            if path
+                .extension()
+                .is_none_or(|extension| extension != "juice")
+            {
+                continue;
+            }
+            let source = std::fs::read_to_string(&path).unwrap();
+            let formatted = format_source(&source).unwrap();
+
+            assert_eq!(format_source(&formatted).unwrap(), formatted, "{:?}", path);
+            let parse = |source: &str| {
+                let mut statements = Parser::new(Lexer::new(source).lex()).parse().unwrap();
+                statements.iter_mut().for_each(strip_statement);
+                statements
+            };
+            assert_eq!(parse(&formatted), parse(&source), "{:?}", path);
+            assert_eq!(
+                formatted.matches("//").count(),
+                source.matches("//").count(),
+                "{:?}",
+                path
+            );
+        }
+    }
+}
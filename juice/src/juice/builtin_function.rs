@@ -1,41 +1,82 @@
-use super::{array::Array, flow::Flow, value::Value};
-use std::{cell::RefCell, rc::Rc};
+use super::{
+    convert::IntoValue, error::Error, flow::Flow, gc, interpreter::Interpreter, value::Value,
+};
+use std::{collections::HashMap, io::Write};
 
 fn str_internal(value: &Value) -> Result<String, Flow> {
     Ok(format!("{}", value))
 }
 
-pub fn str(values: &Vec<Value>) -> Result<Value, Flow> {
-    Ok(Value::String(Rc::new(RefCell::new(str_internal(
-        &values[0],
-    )?))))
+pub fn str(_: &mut Interpreter, values: &[Value]) -> Result<Value, Flow> {
+    str_internal(&values[0])?.into_value()
 }
 
-pub fn assert(values: &Vec<Value>) -> Result<Value, Flow> {
-    let ret = values[0].eq(&values[1])?.as_bool()?;
+pub fn assert(_: &mut Interpreter, values: &[Value]) -> Result<Value, Flow> {
+    if values[0] == values[1] {
+        return Ok(Value::Void);
+    }
+
     let a = str_internal(&values[0])?;
     let b = str_internal(&values[1])?;
-    assert!(ret, "Assertion fialed: {} == {}", a, b);
-    Ok(Value::Void)
+    match values.get(2) {
+        Some(message) => Err(Flow::error(format!(
+            "Assertion failed: {} (left: {}, right: {})",
+            str_internal(message)?,
+            a,
+            b
+        ))),
+        None => Err(Flow::error(format!("Assertion failed: {} == {}", a, b))),
+    }
 }
 
-pub fn addr(values: &Vec<Value>) -> Result<Value, Flow> {
-    let address = format!("{:p}", &values[0]);
-    Ok(Value::String(Rc::new(RefCell::new(address))))
+pub fn addr(_: &mut Interpreter, values: &[Value]) -> Result<Value, Flow> {
+    format!("{:p}", &values[0]).into_value()
 }
 
-pub fn print(values: &Vec<Value>) -> Result<Value, Flow> {
-    let output: Vec<String> = values
+pub fn print(interpreter: &mut Interpreter, values: &[Value]) -> Result<Value, Flow> {
+    let output = values
         .iter()
-        .map(|value| str_internal(value).unwrap())
-        .collect();
+        .map(str_internal)
+        .collect::<Result<Vec<_>, _>>()?;
 
-    println!("{}", output.join(" "));
+    writeln!(interpreter.output, "{}", output.join(" "))
+        .map_err(|err| Flow::error(format!("Error writing output: {}", err)))?;
 
     Ok(Value::Void)
 }
 
-pub fn range(values: &Vec<Value>) -> Result<Value, Flow> {
+pub fn read_line(interpreter: &mut Interpreter, _: &[Value]) -> Result<Value, Flow> {
+    let mut line = String::new();
+    let read = interpreter
+        .input
+        .read_line(&mut line)
+        .map_err(|err| Flow::error(format!("Error reading input: {}", err)))?;
+
+    if read == 0 {
+        return Ok(Value::Null);
+    }
+
+    let line = line.strip_suffix('\n').unwrap_or(&line);
+    line.strip_suffix('\r').unwrap_or(line).into_value()
+}
+
+pub fn input(interpreter: &mut Interpreter, values: &[Value]) -> Result<Value, Flow> {
+    if let Some(prompt) = values.first() {
+        write!(interpreter.output, "{}", str_internal(prompt)?)
+            .and_then(|_| interpreter.output.flush())
+            .map_err(|err| Flow::error(format!("Error writing output: {}", err)))?;
+    }
+    read_line(interpreter, &[])
+}
+
+pub fn range(interpreter: &mut Interpreter, values: &[Value]) -> Result<Value, Flow> {
+    if values
+        .iter()
+        .all(|value| matches!(value, Value::Integer(_)))
+    {
+        return integer_range(interpreter, values);
+    }
+
     let start = values[0].as_number()?;
     let end = values[1].as_number()?;
     let step = if values.len() == 3 {
@@ -44,12 +85,89 @@ pub fn range(values: &Vec<Value>) -> Result<Value, Flow> {
         1.0
     };
 
+    if step <= 0.0 {
+        return Err(Flow::error("Range step must be positive".to_string()));
+    }
+
+    let count = ((end - start) / step).ceil().max(0.0) as usize;
+    interpreter
+        .budget
+        .allocate(count.saturating_mul(std::mem::size_of::<Value>()))?;
+
     let mut range = Vec::new();
     let mut x = start;
     while x < end {
-        range.push(Value::Number(x));
+        range.push(x);
         x += step;
     }
 
-    Ok(Value::Array(Rc::new(RefCell::new(Array::new(range)))))
+    range.into_value()
+}
+
+fn integer_range(interpreter: &mut Interpreter, values: &[Value]) -> Result<Value, Flow> {
+    let start = values[0].as_integer()?;
+    let end = values[1].as_integer()?;
+    let step = match values.get(2) {
+        Some(step) => step.as_integer()?,
+        None => 1,
+    };
+
+    if step <= 0 {
+        return Err(Flow::error("Range step must be positive".to_string()));
+    }
+
+    let count = (end as i128 - start as i128).max(0) as u128 / step as u128;
+    interpreter
+        .budget
+        .allocate((count as usize).saturating_mul(std::mem::size_of::<Value>()))?;
+
+    (start..end)
+        .step_by(step as usize)
+        .collect::<Vec<_>>()
+        .into_value()
+}
+
+fn wrapping(values: &[Value], operation: fn(i64, i64) -> i64) -> Result<Value, Flow> {
+    Ok(Value::Integer(operation(
+        values[0].as_integer()?,
+        values[1].as_integer()?,
+    )))
+}
+
+pub fn wrapping_add(_: &mut Interpreter, values: &[Value]) -> Result<Value, Flow> {
+    wrapping(values, i64::wrapping_add)
+}
+
+pub fn wrapping_sub(_: &mut Interpreter, values: &[Value]) -> Result<Value, Flow> {
+    wrapping(values, i64::wrapping_sub)
+}
+
+pub fn wrapping_mul(_: &mut Interpreter, values: &[Value]) -> Result<Value, Flow> {
+    wrapping(values, i64::wrapping_mul)
+}
+
+pub fn gc(_: &mut Interpreter, _: &[Value]) -> Result<Value, Flow> {
+    Ok(Value::Integer(gc::collect() as i64))
+}
+
+pub fn memory_stats(_: &mut Interpreter, _: &[Value]) -> Result<Value, Flow> {
+    let stats = gc::stats();
+    HashMap::from([
+        ("objects".to_string(), stats.objects as i64),
+        ("arrays".to_string(), stats.arrays as i64),
+        ("collections".to_string(), stats.collections as i64),
+        ("collected".to_string(), stats.collected as i64),
+    ])
+    .into_value()
+}
+
+pub fn exit(_: &mut Interpreter, values: &[Value]) -> Result<Value, Flow> {
+    let code = match values.first() {
+        Some(code) => code.as_integer()?,
+        None => 0,
+    };
+    match i32::try_from(code) {
+        Ok(code) => Err(Flow::Error(Box::new(Error::exit(code)))),
+        Err(_) => Err(Flow::error(format!("Exit code {} is out of range", code))),
+    }
 }
@@ -0,0 +1,253 @@
+use super::{flow::Flow, native_function::NativeFunction, object::Object, value::Value};
+use std::collections::HashMap;
+
+pub trait FromValue: Sized {
+    fn from_value(value: &Value) -> Result<Self, Flow>;
+}
+
+pub trait IntoValue {
+    fn into_value(self) -> Result<Value, Flow>;
+}
+
+fn expected(type_name: &str, value: &Value) -> Flow {
+    Flow::error(format!(
+        "Expected {} but got {}",
+        type_name,
+        value.type_name()
+    ))
+}
+
+impl FromValue for Value {
+    fn from_value(value: &Value) -> Result<Self, Flow> {
+        Ok(value.clone())
+    }
+}
+
+impl IntoValue for Value {
+    fn into_value(self) -> Result<Value, Flow> {
+        Ok(self)
+    }
+}
+
+impl FromValue for f64 {
+    fn from_value(value: &Value) -> Result<Self, Flow> {
+        match value {
+            Value::Number(n) => Ok(*n),
+            Value::Integer(n) => Ok(*n as f64),
+            _ => Err(expected("number", value)),
+        }
+    }
+}
+
+impl IntoValue for f64 {
+    fn into_value(self) -> Result<Value, Flow> {
+        Ok(Value::Number(self))
+    }
+}
+
+impl FromValue for i64 {
+    fn from_value(value: &Value) -> Result<Self, Flow> {
+        value.as_integer()
+    }
+}
+
+impl IntoValue for i64 {
+    fn into_value(self) -> Result<Value, Flow> {
+        Ok(Value::Integer(self))
+    }
+}
+
+impl FromValue for bool {
+    fn from_value(value: &Value) -> Result<Self, Flow> {
+        match value {
+            Value::Bool(b) => Ok(*b),
+            _ => Err(expected("bool", value)),
+        }
+    }
+}
+
+impl IntoValue for bool {
+    fn into_value(self) -> Result<Value, Flow> {
+        Ok(Value::Bool(self))
+    }
+}
+
+impl FromValue for String {
+    fn from_value(value: &Value) -> Result<Self, Flow> {
+        match value {
+            Value::String(s) => Ok(s.to_string()),
+            _ => Err(expected("string", value)),
+        }
+    }
+}
+
+impl IntoValue for String {
+    fn into_value(self) -> Result<Value, Flow> {
+        Ok(Value::new_string(self))
+    }
+}
+
+impl IntoValue for &str {
+    fn into_value(self) -> Result<Value, Flow> {
+        Ok(Value::new_string(self.to_string()))
+    }
+}
+
+impl IntoValue for () {
+    fn into_value(self) -> Result<Value, Flow> {
+        Ok(Value::Void)
+    }
+}
+
+impl<T: FromValue> FromValue for Vec<T> {
+    fn from_value(value: &Value) -> Result<Self, Flow> {
+        match value {
+            Value::Array(array) => array.borrow().elements.iter().map(T::from_value).collect(),
+            _ => Err(expected("array", value)),
+        }
+    }
+}
+
+impl<T: IntoValue> IntoValue for Vec<T> {
+    fn into_value(self) -> Result<Value, Flow> {
+        let elements = self
+            .into_iter()
+            .map(T::into_value)
+            .collect::<Result<Vec<_>, _>>()?;
+        Value::new_array(elements)
+    }
+}
+
+impl<T: FromValue> FromValue for Option<T> {
+    fn from_value(value: &Value) -> Result<Self, Flow> {
+        match value {
+            Value::Null => Ok(None),
+            _ => Ok(Some(T::from_value(value)?)),
+        }
+    }
+}
+
+impl<T: IntoValue> IntoValue for Option<T> {
+    fn into_value(self) -> Result<Value, Flow> {
+        match self {
+            Some(value) => value.into_value(),
+            None => Ok(Value::Null),
+        }
+    }
+}
+
+impl<T: FromValue> FromValue for HashMap<String, T> {
+    fn from_value(value: &Value) -> Result<Self, Flow> {
+        match value {
+            Value::Object(object) => object
+                .borrow()
+                .values
+                .iter()
+                .map(|(name, value)| Ok((name.to_string(), T::from_value(value)?)))
+                .collect(),
+            _ => Err(expected("object", value)),
+        }
+    }
+}
+
+impl<T: IntoValue> IntoValue for HashMap<String, T> {
+    fn into_value(self) -> Result<Value, Flow> {
+        let mut object = Object::new();
+        for (name, value) in self {
+            object.define_value(name, value.into_value()?)?;
+        }
+        Ok(Value::new_object(object))
+    }
+}
+
+impl<T: IntoValue> IntoValue for Result<T, Flow> {
+    fn into_value(self) -> Result<Value, Flow> {
+        self?.into_value()
+    }
+}
+
+/// Converts a typed Rust function such as `fn(f64, f64) -> f64` into a native function whose
+/// arity and argument types are checked from its signature.
+pub trait IntoNativeFunction<Args> {
+    fn into_native_function(self) -> NativeFunction;
+}
+
+fn argument<T: FromValue>(arguments: &[Value], index: usize) -> Result<T, Flow> {
+    T::from_value(&arguments[index]).map_err(|flow| match flow {
+        Flow::Error(mut error) => {
+            error.message = format!("Argument {}: {}", index + 1, error.message);
+            Flow::Error(error)
+        }
+        flow => flow,
+    })
+}
+
+macro_rules! impl_into_native_function {
+    ($($arg:ident $index:tt),*) => {
+        impl<F, R, $($arg),*> IntoNativeFunction<($($arg,)*)> for F
+        where
+            F: Fn($($arg),*) -> R + 'static,
+            R: IntoValue,
+            $($arg: FromValue),*
+        {
+            #[allow(unused_variables)]
+            fn into_native_function(self) -> NativeFunction {
+                let arity = 0 $(+ { let _ = stringify!($arg); 1 })*;
+                NativeFunction::new(
+                    move |_, arguments| {
+                        (self)($(argument::<$arg>(arguments, $index)?),*).into_value()
+                    },
+                    arity,
+                    arity,
+                )
+            }
+        }
+    };
+}
+
+impl_into_native_function!();
+impl_into_native_function!(A 0);
+impl_into_native_function!(A 0, B 1);
+impl_into_native_function!(A 0, B 1, C 2);
+impl_into_native_function!(A 0, B 1, C 2, D 3);
+impl_into_native_function!(A 0, B 1, C 2, D 3, E 4);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_round_trip() {
+        let value = vec![1.0, 2.5].into_value().unwrap();
+        assert_eq!(Vec::<f64>::from_value(&value).unwrap(), vec![1.0, 2.5]);
+
+        let value = HashMap::from([("name".to_string(), "juice")])
+            .into_value()
+            .unwrap();
+        let map = HashMap::<String, String>::from_value(&value).unwrap();
+        assert_eq!(map["name"], "juice");
+
+        let value = Option::<i64>::None.into_value().unwrap();
+        assert_eq!(value, Value::Null);
+        assert_eq!(
+            Option::<i64>::from_value(&Value::Number(3.0)).unwrap(),
+            Some(3)
+        );
+    }
+
+    #[test]
+    fn test_type_errors() {
+        assert_eq!(
+            bool::from_value(&Value::Number(1.0)),
+            Err(Flow::error("Expected bool but got number".to_string()))
+        );
+        assert_eq!(
+            i64::from_value(&Value::Number(1.5)),
+            Err(Flow::error("Expected integer but got 1.5".to_string()))
+        );
+        assert_eq!(
+            Vec::<String>::from_value(&vec![1.0].into_value().unwrap()),
+            Err(Flow::error("Expected string but got number".to_string()))
+        );
+    }
+}
@@ -1,6 +1,13 @@
-use super::{flow::Flow, object::Object, value::Value};
+use super::{flow::Flow, object::Object, symbol::Symbol, value::Value};
 use std::{cell::RefCell, rc::Rc};
 
+// The bottom scope is a prelude for standard library namespaces such as `math`, so
+// scripts can still declare globals with the same names in the scope above it.
+const GLOBAL: usize = 1;
+
+/// The depth of an environment holding only the prelude and global scopes, which are never popped.
+pub const BASE_DEPTH: usize = GLOBAL + 1;
+
 pub struct Env {
     stack: Vec<Rc<RefCell<Object>>>,
 }
@@ -8,7 +15,10 @@ pub struct Env {
 impl Env {
     pub fn new() -> Self {
         Self {
-            stack: vec![Rc::new(RefCell::new(Object::new()))],
+            stack: vec![
+                Rc::new(RefCell::new(Object::new())),
+                Rc::new(RefCell::new(Object::new())),
+            ],
         }
     }
 
@@ -21,8 +31,8 @@ impl Env {
     }
 
     pub fn pop(&mut self) -> Result<Value, Flow> {
-        if self.stack.len() == 1 {
-            return Err(Flow::Error(
+        if self.stack.len() == BASE_DEPTH {
+            return Err(Flow::error(
                 "Cannot pop the default environment".to_string(),
             ));
         }
@@ -30,21 +40,37 @@ impl Env {
         Ok(Value::Void)
     }
 
+    pub fn depth(&self) -> usize {
+        self.stack.len()
+    }
+
+    pub fn truncate(&mut self, depth: usize) {
+        self.stack.truncate(depth.max(BASE_DEPTH));
+    }
+
     pub fn current(&self) -> Rc<RefCell<Object>> {
         self.stack.last().unwrap().clone()
     }
 
-    pub fn define_value(&mut self, name: String, value: Value) -> Result<Value, Flow> {
+    pub fn global(&self) -> Rc<RefCell<Object>> {
+        self.stack[GLOBAL].clone()
+    }
+
+    pub fn prelude(&self) -> Rc<RefCell<Object>> {
+        self.stack[0].clone()
+    }
+
+    pub fn define_value(&mut self, name: impl Into<Symbol>, value: Value) -> Result<Value, Flow> {
         self.current().borrow_mut().define_value(name, value)
     }
 
-    pub fn set_value(&mut self, name: String, value: Value) -> Result<Value, Flow> {
+    pub fn set_value(&mut self, name: &str, value: Value) -> Result<Value, Flow> {
         for object in self.stack.iter().rev() {
-            if object.borrow().values.contains_key(&name) {
+            if object.borrow().values.contains_key(name) {
                 return object.borrow_mut().set_value(name, value);
             }
         }
-        Err(Flow::Error(format!("Variable {} not found", name)))
+        Err(Flow::error(format!("Variable {} not found", name)))
     }
 
     pub fn get_value(&self, name: &str) -> Result<Value, Flow> {
@@ -53,19 +79,19 @@ impl Env {
                 return Ok(value);
             }
         }
-        Err(Flow::Error(format!("Variable {} not found", name)))
+        Err(Flow::error(format!("Variable {} not found", name)))
     }
 
     pub fn get_method(&self, name: &str) -> Result<Value, Flow> {
         for object in self.stack.iter().rev() {
-            if let Ok(value) = object.borrow().get_method(name) {
+            if let Ok(value) = Object::get_bound_method(object, name) {
                 return Ok(value);
             }
         }
-        Err(Flow::Error(format!("Method {} not found", name)))
+        Err(Flow::error(format!("Method {} not found", name)))
     }
 
-    pub fn define_method(&mut self, name: String, method: Value) -> Result<Value, Flow> {
+    pub fn define_method(&mut self, name: impl Into<Symbol>, method: Value) -> Result<Value, Flow> {
         self.current().borrow_mut().define_method(name, method)
     }
 }
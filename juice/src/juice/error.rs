@@ -0,0 +1,120 @@
+use super::{flow::Flow, object::Object, value::Value};
+use std::fmt::{Debug, Display};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ErrorKind {
+    Syntax,
+    Runtime,
+    Control,
+    RecursionLimit,
+    StepLimit,
+    MemoryLimit,
+    Interrupted,
+    Permission,
+    Exit,
+    Thrown,
+}
+
+/// A failure reported to the host after a script stopped executing.
+#[derive(Clone, PartialEq)]
+pub struct Error {
+    pub kind: ErrorKind,
+    pub message: String,
+    pub value: Option<Value>,
+    pub stack: Vec<String>,
+}
+
+impl Error {
+    pub fn new(kind: ErrorKind, message: impl Into<String>) -> Self {
+        Self {
+            kind,
+            message: message.into(),
+            value: None,
+            stack: Vec::new(),
+        }
+    }
+
+    pub fn thrown(value: Value) -> Self {
+        let message = match &value {
+            Value::Object(object) => match object.borrow().values.get("message") {
+                Some(message) => message.to_string(),
+                None => value.to_string(),
+            },
+            _ => value.to_string(),
+        };
+        Self {
+            value: Some(value),
+            ..Self::new(ErrorKind::Thrown, message)
+        }
+    }
+
+    /// A script calling `exit(code)`; it unwinds like an error so the host decides what exiting means.
+    pub fn exit(code: i32) -> Self {
+        Self {
+            value: Some(Value::Integer(code as i64)),
+            ..Self::new(ErrorKind::Exit, format!("Script exited with code {}", code))
+        }
+    }
+
+    pub fn exit_code(&self) -> Option<i32> {
+        match (self.kind, &self.value) {
+            (ErrorKind::Exit, Some(Value::Integer(code))) => Some(*code as i32),
+            _ => None,
+        }
+    }
+
+    pub fn is_catchable(&self) -> bool {
+        !matches!(self.kind, ErrorKind::Interrupted | ErrorKind::Exit)
+    }
+
+    /// The value a `catch` clause receives: whatever was thrown, or an error object
+    /// with `message`, `kind` and `stack` fields for errors raised by the interpreter.
+    pub fn to_value(&self) -> Result<Value, Flow> {
+        if let Some(value) = &self.value {
+            return Ok(value.clone());
+        }
+
+        let stack = self
+            .stack
+            .iter()
+            .map(|frame| Value::new_string(frame.clone()))
+            .collect();
+
+        let mut object = Object::new();
+        object.define_value(
+            "message".to_string(),
+            Value::new_string(self.message.clone()),
+        )?;
+        object.define_value(
+            "kind".to_string(),
+            Value::new_string(format!("{:?}", self.kind)),
+        )?;
+        object.define_value("stack".to_string(), Value::new_array(stack)?)?;
+        Ok(Value::new_object(object))
+    }
+}
+
+impl From<Flow> for Error {
+    fn from(flow: Flow) -> Self {
+        match flow {
+            Flow::Error(error) => *error,
+            Flow::Return(_) => Error::new(ErrorKind::Control, "Return statement outside of method"),
+            Flow::Break => Error::new(ErrorKind::Control, "Break statement outside of loop"),
+            Flow::Continue => Error::new(ErrorKind::Control, "Continue statement outside of loop"),
+        }
+    }
+}
+
+impl Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.message)
+    }
+}
+
+impl Debug for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:?}({})", self.kind, self.message)
+    }
+}
+
+impl std::error::Error for Error {}
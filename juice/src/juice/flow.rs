@@ -1,12 +1,25 @@
 use std::fmt::Debug;
-use super::value::Value;
+use super::{
+    error::{Error, ErrorKind},
+    value::Value,
+};
 
 #[derive(Clone, PartialEq)]
 pub enum Flow {
     Return(Value),
     Break,
     Continue,
-    Error(String),
+    Error(Box<Error>),
+}
+
+impl Flow {
+    pub fn error(message: impl Into<String>) -> Self {
+        Flow::Error(Box::new(Error::new(ErrorKind::Runtime, message)))
+    }
+
+    pub fn error_of_kind(kind: ErrorKind, message: impl Into<String>) -> Self {
+        Flow::Error(Box::new(Error::new(kind, message)))
+    }
 }
 
 impl Debug for Flow {
@@ -15,7 +28,7 @@ impl Debug for Flow {
             Flow::Return(value) => write!(f, "Return({})", value),
             Flow::Break => write!(f, "Break"),
             Flow::Continue => write!(f, "Continue"),
-            Flow::Error(message) => write!(f, "Error({})", message),
+            Flow::Error(error) => write!(f, "Error({:?})", error),
         }
     }
 }
@@ -0,0 +1,257 @@
+use super::{
+    error::ErrorKind, flow::Flow, interpreter::Interpreter, native_function::NativeFunction,
+    object::Object, value::Value,
+};
+use std::{
+    fs, io,
+    path::{Component, Path, PathBuf},
+    rc::Rc,
+};
+
+/// The directory scripts may touch; every path is resolved against it and must stay inside it,
+/// including through `..` and symlinks.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Sandbox {
+    root: PathBuf,
+}
+
+impl Sandbox {
+    pub fn new(root: impl AsRef<Path>) -> Result<Self, Flow> {
+        let root = root.as_ref();
+        match root.canonicalize() {
+            Ok(root) if root.is_dir() => Ok(Self { root }),
+            Ok(_) => Err(Flow::error(format!(
+                "File system root {} is not a directory",
+                root.display()
+            ))),
+            Err(err) => Err(Flow::error(format!(
+                "Cannot open file system root {}: {}",
+                root.display(),
+                err
+            ))),
+        }
+    }
+
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    /// Maps a script path to a real one, rejecting anything that escapes the root.
+    pub fn resolve(&self, path: &str) -> Result<PathBuf, Flow> {
+        let joined = self.root.join(path);
+        let resolved = match joined.canonicalize() {
+            Ok(resolved) => resolved,
+            // A dangling symlink would be followed on write, wherever it points.
+            Err(_) if is_symlink(&joined) => return Err(self.denied(path)),
+            // The target may not exist yet, as for `writeText`, so resolve its parent instead.
+            Err(_) => match (joined.parent(), joined.file_name()) {
+                (Some(parent), Some(name)) => match parent.canonicalize() {
+                    Ok(parent) => parent.join(name),
+                    Err(_) => normalize(&joined),
+                },
+                _ => normalize(&joined),
+            },
+        };
+        self.check(path, resolved)
+    }
+
+    /// Like `resolve`, but leaves the last component alone, so a symlink names the link itself.
+    pub fn resolve_entry(&self, path: &str) -> Result<PathBuf, Flow> {
+        let joined = normalize(&self.root.join(path));
+        match (joined.parent(), joined.file_name()) {
+            (Some(parent), Some(name)) => {
+                let parent = parent
+                    .canonicalize()
+                    .unwrap_or_else(|_| parent.to_path_buf());
+                self.check(path, parent.join(name))
+            }
+            _ => Err(self.denied(path)),
+        }
+    }
+
+    fn check(&self, path: &str, resolved: PathBuf) -> Result<PathBuf, Flow> {
+        if resolved.starts_with(&self.root) {
+            Ok(resolved)
+        } else {
+            Err(self.denied(path))
+        }
+    }
+
+    fn denied(&self, path: &str) -> Flow {
+        Flow::error_of_kind(
+            ErrorKind::Permission,
+            format!(
+                "Permission denied: {} is outside the allowed directory",
+                path
+            ),
+        )
+    }
+}
+
+fn is_symlink(path: &Path) -> bool {
+    fs::symlink_metadata(path).is_ok_and(|metadata| metadata.file_type().is_symlink())
+}
+
+// Collapses `.` and `..` without touching the file system, for paths that do not exist.
+fn normalize(path: &Path) -> PathBuf {
+    let mut normalized = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => {
+                normalized.pop();
+            }
+            component => normalized.push(component),
+        }
+    }
+    normalized
+}
+
+/// Builds the `fs` namespace object that `with_fs` exposes to scripts.
+pub fn module(sandbox: Sandbox) -> Result<Value, Flow> {
+    let sandbox = Rc::new(sandbox);
+    let mut fs = Object::new();
+    define(
+        &mut fs,
+        &sandbox,
+        "readText",
+        1,
+        Sandbox::resolve,
+        read_text,
+    )?;
+    define(
+        &mut fs,
+        &sandbox,
+        "writeText",
+        2,
+        Sandbox::resolve,
+        write_text,
+    )?;
+    define(
+        &mut fs,
+        &sandbox,
+        "readLines",
+        1,
+        Sandbox::resolve,
+        read_lines,
+    )?;
+    define(&mut fs, &sandbox, "exists", 1, Sandbox::resolve, exists)?;
+    define(&mut fs, &sandbox, "listDir", 1, Sandbox::resolve, list_dir)?;
+    // Removing a symlink removes the link, never what it points to.
+    define(
+        &mut fs,
+        &sandbox,
+        "remove",
+        1,
+        Sandbox::resolve_entry,
+        remove,
+    )?;
+    Ok(Value::new_object(fs))
+}
+
+type ResolveFn = fn(&Sandbox, &str) -> Result<PathBuf, Flow>;
+type FsFn = fn(&mut Interpreter, &Sandbox, &Path, &[Value]) -> Result<Value, Flow>;
+
+fn define(
+    fs: &mut Object,
+    sandbox: &Rc<Sandbox>,
+    name: &str,
+    arity: usize,
+    resolve: ResolveFn,
+    function: FsFn,
+) -> Result<Value, Flow> {
+    let sandbox = sandbox.clone();
+    let native_function = NativeFunction::new(
+        move |interpreter, values| {
+            let path = resolve(&sandbox, &values[0].as_string()?.as_str())?;
+            function(interpreter, &sandbox, &path, values)
+        },
+        arity,
+        arity,
+    );
+    fs.define_method(name, Value::new_native_function(native_function))
+}
+
+fn io_error(action: &str, values: &[Value], err: io::Error) -> Flow {
+    Flow::error(format!("Cannot {} {}: {}", action, values[0], err))
+}
+
+fn read_text(
+    interpreter: &mut Interpreter,
+    _: &Sandbox,
+    path: &Path,
+    values: &[Value],
+) -> Result<Value, Flow> {
+    let text = fs::read_to_string(path).map_err(|err| io_error("read", values, err))?;
+    interpreter.budget.allocate(text.len())?;
+    Ok(Value::new_string(text))
+}
+
+fn write_text(
+    _: &mut Interpreter,
+    _: &Sandbox,
+    path: &Path,
+    values: &[Value],
+) -> Result<Value, Flow> {
+    let text = values[1].as_string()?;
+    fs::write(path, text.as_str().as_bytes()).map_err(|err| io_error("write", values, err))?;
+    Ok(Value::Void)
+}
+
+fn read_lines(
+    interpreter: &mut Interpreter,
+    _: &Sandbox,
+    path: &Path,
+    values: &[Value],
+) -> Result<Value, Flow> {
+    let text = fs::read_to_string(path).map_err(|err| io_error("read", values, err))?;
+    interpreter.budget.allocate(text.len())?;
+    let lines = text
+        .lines()
+        .map(|line| Value::new_string(line.to_string()))
+        .collect();
+    Value::new_array(lines)
+}
+
+fn exists(_: &mut Interpreter, _: &Sandbox, path: &Path, _: &[Value]) -> Result<Value, Flow> {
+    Ok(Value::Bool(path.exists()))
+}
+
+fn list_dir(
+    interpreter: &mut Interpreter,
+    _: &Sandbox,
+    path: &Path,
+    values: &[Value],
+) -> Result<Value, Flow> {
+    let entries = fs::read_dir(path).map_err(|err| io_error("list", values, err))?;
+    let mut names = Vec::new();
+    for entry in entries {
+        let entry = entry.map_err(|err| io_error("list", values, err))?;
+        names.push(entry.file_name().to_string_lossy().into_owned());
+    }
+    // Directory order is platform dependent, so sort for reproducible scripts.
+    names.sort();
+    interpreter
+        .budget
+        .allocate(names.iter().map(String::len).sum())?;
+    Value::new_array(names.into_iter().map(Value::new_string).collect())
+}
+
+fn remove(
+    _: &mut Interpreter,
+    sandbox: &Sandbox,
+    path: &Path,
+    values: &[Value],
+) -> Result<Value, Flow> {
+    if path == sandbox.root() {
+        return Err(sandbox.denied(&values[0].to_string()));
+    }
+    let metadata = fs::symlink_metadata(path).map_err(|err| io_error("remove", values, err))?;
+    let result = if metadata.is_dir() {
+        fs::remove_dir(path)
+    } else {
+        fs::remove_file(path)
+    };
+    result.map_err(|err| io_error("remove", values, err))?;
+    Ok(Value::Void)
+}
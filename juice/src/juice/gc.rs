@@ -0,0 +1,242 @@
+use super::{array::Array, object::Object, value::Value};
+use std::{
+    cell::RefCell,
+    collections::HashMap,
+    mem,
+    rc::{Rc, Weak},
+};
+
+const MIN_THRESHOLD: usize = 10_000;
+
+thread_local! {
+    static HEAP: RefCell<Heap> = RefCell::new(Heap::new());
+}
+
+// Every object and array is registered here so that reference cycles, which `Rc`
+// alone never frees, can be found and broken.
+struct Heap {
+    objects: Vec<Weak<RefCell<Object>>>,
+    arrays: Vec<Weak<RefCell<Array>>>,
+    threshold: usize,
+    collections: usize,
+    collected: usize,
+}
+
+impl Heap {
+    fn new() -> Self {
+        Self {
+            objects: Vec::new(),
+            arrays: Vec::new(),
+            threshold: MIN_THRESHOLD,
+            collections: 0,
+            collected: 0,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MemoryStats {
+    pub objects: usize,
+    pub arrays: usize,
+    pub collections: usize,
+    pub collected: usize,
+}
+
+enum Node {
+    Object(Rc<RefCell<Object>>),
+    Array(Rc<RefCell<Array>>),
+}
+
+impl Node {
+    fn address(&self) -> usize {
+        match self {
+            Node::Object(object) => Rc::as_ptr(object) as *const () as usize,
+            Node::Array(array) => Rc::as_ptr(array) as *const () as usize,
+        }
+    }
+
+    fn strong_count(&self) -> usize {
+        match self {
+            Node::Object(object) => Rc::strong_count(object),
+            Node::Array(array) => Rc::strong_count(array),
+        }
+    }
+
+    // Addresses of the nodes this one references. `None` means the node is borrowed
+    // right now, so it is treated as reachable and left untouched.
+    fn children(&self, owned_only: bool) -> Option<Vec<usize>> {
+        let mut children = Vec::new();
+        match self {
+            Node::Object(object) => {
+                let object = object.try_borrow().ok()?;
+                for value in object.values.values().chain(object.methods.values()) {
+                    value_children(value, owned_only, &mut children);
+                }
+            }
+            Node::Array(array) => {
+                let array = array.try_borrow().ok()?;
+                for value in &array.elements {
+                    value_children(value, owned_only, &mut children);
+                }
+            }
+        }
+        Some(children)
+    }
+}
+
+// With `owned_only`, references held through a bound method are only followed when
+// nothing else shares that method, so a method value on the Rust stack keeps its
+// object alive.
+fn value_children(value: &Value, owned_only: bool, children: &mut Vec<usize>) {
+    match value {
+        Value::Object(object) => children.push(Rc::as_ptr(object) as *const () as usize),
+        Value::Array(array) => children.push(Rc::as_ptr(array) as *const () as usize),
+        Value::Method(method) if !owned_only || Rc::strong_count(method) == 1 => {
+            if let Ok(method) = method.try_borrow() {
+                if let Some(this) = &method.this {
+                    children.push(Rc::as_ptr(this) as *const () as usize);
+                }
+            }
+        }
+        Value::NativeMethod(method) if !owned_only || Rc::strong_count(method) == 1 => {
+            if let Ok(method) = method.try_borrow() {
+                if !owned_only || Rc::strong_count(&method.this) == 1 {
+                    if let Ok(this) = method.this.try_borrow() {
+                        value_children(&this, owned_only, children);
+                    }
+                }
+            }
+        }
+        _ => {}
+    }
+}
+
+pub fn track_object(object: &Rc<RefCell<Object>>) {
+    let _ = HEAP.try_with(|heap| heap.borrow_mut().objects.push(Rc::downgrade(object)));
+}
+
+pub fn track_array(array: &Rc<RefCell<Array>>) {
+    let _ = HEAP.try_with(|heap| heap.borrow_mut().arrays.push(Rc::downgrade(array)));
+}
+
+pub fn should_collect() -> bool {
+    HEAP.try_with(|heap| {
+        let heap = heap.borrow();
+        heap.objects.len() + heap.arrays.len() >= heap.threshold
+    })
+    .unwrap_or(false)
+}
+
+pub fn stats() -> MemoryStats {
+    HEAP.with(|heap| {
+        let heap = heap.borrow();
+        MemoryStats {
+            objects: heap.objects.iter().filter(|o| o.strong_count() > 0).count(),
+            arrays: heap.arrays.iter().filter(|a| a.strong_count() > 0).count(),
+            collections: heap.collections,
+            collected: heap.collected,
+        }
+    })
+}
+
+/// Frees objects and arrays that are only reachable from each other and returns how
+/// many were freed. Anything referenced from outside the heap, such as the
+/// environment or a value held by the host, counts as a root.
+pub fn collect() -> usize {
+    let nodes: Vec<Node> = HEAP.with(|heap| {
+        let mut heap = heap.borrow_mut();
+        heap.objects.retain(|object| object.strong_count() > 0);
+        heap.arrays.retain(|array| array.strong_count() > 0);
+        let objects = heap
+            .objects
+            .iter()
+            .filter_map(Weak::upgrade)
+            .map(Node::Object);
+        let arrays = heap
+            .arrays
+            .iter()
+            .filter_map(Weak::upgrade)
+            .map(Node::Array);
+        objects.chain(arrays).collect()
+    });
+
+    let index: HashMap<usize, usize> = nodes
+        .iter()
+        .enumerate()
+        .map(|(i, node)| (node.address(), i))
+        .collect();
+
+    // Trial deletion: references coming from other heap nodes are subtracted from each
+    // strong count, and whatever is left over must come from a root.
+    let mut internal = vec![0; nodes.len()];
+    let mut borrowed = vec![false; nodes.len()];
+    for (i, node) in nodes.iter().enumerate() {
+        match node.children(true) {
+            Some(children) => {
+                for child in children {
+                    if let Some(&child) = index.get(&child) {
+                        internal[child] += 1;
+                    }
+                }
+            }
+            None => borrowed[i] = true,
+        }
+    }
+
+    let mut reachable = vec![false; nodes.len()];
+    let mut stack: Vec<usize> = (0..nodes.len())
+        // One strong reference is the upgraded `Rc` held in `nodes`.
+        .filter(|&i| borrowed[i] || nodes[i].strong_count() - 1 > internal[i])
+        .collect();
+    while let Some(i) = stack.pop() {
+        if mem::replace(&mut reachable[i], true) {
+            continue;
+        }
+        for child in nodes[i].children(false).unwrap_or_default() {
+            if let Some(&child) = index.get(&child) {
+                if !reachable[child] {
+                    stack.push(child);
+                }
+            }
+        }
+    }
+
+    // Emptying the unreachable nodes breaks their cycles; the contents are dropped only
+    // once every node has been emptied.
+    let mut objects = Vec::new();
+    let mut elements = Vec::new();
+    for (i, node) in nodes.iter().enumerate() {
+        if reachable[i] {
+            continue;
+        }
+        match node {
+            Node::Object(object) => {
+                // Moving the deinit queue along means `deinit` still sees the fields.
+                let mut object = object.borrow_mut();
+                objects.push(Object {
+                    values: mem::take(&mut object.values),
+                    methods: mem::take(&mut object.methods),
+                    prototype: object.prototype.take(),
+                    deinit: object.deinit.take(),
+                });
+            }
+            Node::Array(array) => {
+                elements.extend(mem::take(&mut array.borrow_mut().elements));
+            }
+        }
+    }
+
+    let collected = nodes.len() - reachable.iter().filter(|&&r| r).count();
+    let live = nodes.len() - collected;
+    drop(nodes);
+    drop(objects);
+    drop(elements);
+
+    HEAP.with(|heap| {
+        let mut heap = heap.borrow_mut();
+        heap.collections += 1;
+        heap.collected += collected;
+        heap.threshold = MIN_THRESHOLD.max(live * 2);
+    });
+    collected
+}
@@ -1,13 +1,51 @@
 use super::{
-    builtin_function, env::Env, flow::Flow, method::Method, native_function::NativeFunction,
-    object::Object, traits::Callable, value::Value,
+    array::Array,
+    builtin_function,
+    convert::IntoNativeFunction,
+    env::{Env, BASE_DEPTH},
+    error::{Error, ErrorKind},
+    flow::Flow,
+    fs::{self, Sandbox},
+    gc,
+    interrupt::InterruptHandle,
+    json,
+    limits::{Budget, Limits},
+    math,
+    method::Method,
+    native_function::NativeFunction,
+    native_object::{NativeObject, NativeType},
+    object::{DeinitQueue, Object},
+    process,
+    random::{self, Random},
+    stream::OutputBuffer,
+    string::Str,
+    symbol::Symbol,
+    time::{self, Clock, SystemClock},
+    traits::Callable,
+    value::Value,
+};
+use crate::ast::{
+    BinaryOp, Block, Expression, MethodDeclaration, MethodSignature, Spanned, Statement,
+    StatementKind, UnaryOp,
+};
+use std::{
+    cell::RefCell,
+    collections::HashMap,
+    io::{self, BufRead, BufReader, Write},
+    mem,
+    rc::Rc,
 };
-use crate::ast::{BinaryOp, Expression, MethodDeclaration, MethodSignature, Statement, UnaryOp};
-use std::{cell::RefCell, collections::HashMap, rc::Rc};
 
 pub struct Interpreter {
     pub env: Env,
-    pub prototypes: HashMap<String, Object>,
+    pub prototypes: HashMap<String, Rc<Object>>,
+    pub native_types: HashMap<String, Rc<NativeType>>,
+    pub traits: HashMap<String, Vec<MethodSignature>>,
+    pub output: Box<dyn Write>,
+    pub input: Box<dyn BufRead>,
+    pub budget: Budget,
+    pub clock: Rc<dyn Clock>,
+    pub pending_deinit: DeinitQueue,
 }
 
 impl Interpreter {
@@ -15,38 +53,245 @@ impl Interpreter {
         Self {
             env: Env::new(),
             prototypes: HashMap::new(),
+            native_types: HashMap::new(),
+            traits: HashMap::new(),
+            output: Box::new(io::stdout()),
+            input: Box::new(BufReader::new(io::stdin())),
+            budget: Budget::default(),
+            clock: Rc::new(SystemClock::new()),
+            pending_deinit: DeinitQueue::new(),
         }
     }
 
     pub fn with_std(&mut self) -> Result<&mut Self, Flow> {
         self.with_std_function("str", builtin_function::str, 1, 1)?;
-        self.with_std_function("assert", builtin_function::assert, 2, 2)?;
+        self.with_std_function("assert", builtin_function::assert, 2, 3)?;
         self.with_std_function("addr", builtin_function::addr, 1, 1)?;
         self.with_std_function("print", builtin_function::print, 0, 256)?;
         self.with_std_function("range", builtin_function::range, 2, 3)?;
+        self.with_std_function("readLine", builtin_function::read_line, 0, 0)?;
+        self.with_std_function("input", builtin_function::input, 0, 1)?;
+        self.with_std_function("wrappingAdd", builtin_function::wrapping_add, 2, 2)?;
+        self.with_std_function("wrappingSub", builtin_function::wrapping_sub, 2, 2)?;
+        self.with_std_function("wrappingMul", builtin_function::wrapping_mul, 2, 2)?;
+        self.with_std_function("gc", builtin_function::gc, 0, 0)?;
+        self.with_std_function("memoryStats", builtin_function::memory_stats, 0, 0)?;
+        self.with_std_function("exit", builtin_function::exit, 0, 1)?;
+        self.env
+            .prelude()
+            .borrow_mut()
+            .define_value("math", math::module()?)?;
+        self.env
+            .prelude()
+            .borrow_mut()
+            .define_value("json", json::module()?)?;
+        self.env
+            .prelude()
+            .borrow_mut()
+            .define_value("time", time::module()?)?;
+        self.register_native_type(time::stopwatch_type())?;
+        self.register_native_type(random::native_type())?;
+        let random = self.new_native_object("Random", Random::from_time())?;
+        self.env
+            .prelude()
+            .borrow_mut()
+            .define_value("random", random)?;
         Ok(self)
     }
 
+    /// Sends everything scripts print to `output` instead of stdout.
+    pub fn with_output(&mut self, output: impl Write + 'static) -> &mut Self {
+        self.output = Box::new(output);
+        self
+    }
+
+    /// Reads `readLine`/`input` from `input` instead of stdin.
+    pub fn with_input(&mut self, input: impl BufRead + 'static) -> &mut Self {
+        self.input = Box::new(input);
+        self
+    }
+
+    /// Exposes `args` to scripts as an array of strings, e.g. the command line after `--`.
+    pub fn with_args(
+        &mut self,
+        args: impl IntoIterator<Item = impl Into<String>>,
+    ) -> Result<&mut Self, Flow> {
+        let args = process::arguments(args)?;
+        self.env.prelude().borrow_mut().define_value("args", args)?;
+        Ok(self)
+    }
+
+    /// Enables `env.get(name)`; scripts cannot read environment variables otherwise.
+    pub fn with_env(&mut self) -> Result<&mut Self, Flow> {
+        let env = process::environment()?;
+        self.env.prelude().borrow_mut().define_value("env", env)?;
+        Ok(self)
+    }
+
+    /// Makes `time` and `Stopwatch` read from `clock`, e.g. a `ManualClock` in tests. Set it
+    /// before running scripts: a `Stopwatch` started on the old clock reads nonsense on the new one.
+    pub fn with_clock(&mut self, clock: impl Clock + 'static) -> &mut Self {
+        self.clock = Rc::new(clock);
+        self
+    }
+
+    /// Seeds the default `random` generator so runs are reproducible; call after `with_std`.
+    pub fn with_seed(&mut self, seed: u64) -> Result<&mut Self, Flow> {
+        let random = self.env.prelude().borrow().get_value("random")?;
+        let random = random.as_native_object()?;
+        match random.borrow_mut().downcast_mut::<Random>() {
+            Some(random) => random.seed(seed),
+            None => return Err(Flow::error("Default generator is not a Random".to_string())),
+        }
+        Ok(self)
+    }
+
+    /// Enables the `fs` module, confined to files under `root`; scripts get no file access otherwise.
+    pub fn with_fs(&mut self, root: impl AsRef<std::path::Path>) -> Result<&mut Self, Flow> {
+        let fs = fs::module(Sandbox::new(root)?)?;
+        self.env.prelude().borrow_mut().define_value("fs", fs)?;
+        Ok(self)
+    }
+
+    /// Bounds call depth, steps and allocations of every subsequent run.
+    pub fn with_limits(&mut self, limits: Limits) -> &mut Self {
+        self.budget.limits = limits;
+        self
+    }
+
+    /// Returns a handle that stops the current run, or the next one if none is running, from
+    /// another thread. The interrupt stays set until a run has stopped on it.
+    pub fn interrupt_handle(&self) -> InterruptHandle {
+        self.budget.interrupt.clone()
+    }
+
+    /// Redirects output into a buffer whose contents can be read back as a `String`.
+    pub fn capture_output(&mut self) -> OutputBuffer {
+        let buffer = OutputBuffer::new();
+        self.with_output(buffer.clone());
+        buffer
+    }
+
     fn with_std_function(
         &mut self,
         name: &str,
-        function: fn(&Vec<Value>) -> Result<Value, Flow>,
+        function: impl Fn(&mut Interpreter, &[Value]) -> Result<Value, Flow> + 'static,
         min_arity: usize,
         max_arity: usize,
     ) -> Result<&mut Self, Flow> {
         let native_function = NativeFunction::new(function, min_arity, max_arity);
-        self.env.define_method(
+        self.env.global().borrow_mut().define_method(
             name.to_string(),
             Value::new_native_function(native_function),
         )?;
         Ok(self)
     }
 
+    /// Exposes a host closure to scripts as a global function taking exactly `arity` arguments.
+    pub fn register_function(
+        &mut self,
+        name: &str,
+        arity: usize,
+        function: impl Fn(&mut Interpreter, &[Value]) -> Result<Value, Flow> + 'static,
+    ) -> Result<&mut Self, Flow> {
+        self.with_std_function(name, function, arity, arity)
+    }
+
+    /// Exposes a typed host function, e.g. `|x: f64, y: f64| x.max(y)`, converting its arguments and result.
+    pub fn register_typed_function<Args>(
+        &mut self,
+        name: &str,
+        function: impl IntoNativeFunction<Args>,
+    ) -> Result<&mut Self, Flow> {
+        self.env.global().borrow_mut().define_method(
+            name.to_string(),
+            Value::new_native_function(function.into_native_function()),
+        )?;
+        Ok(self)
+    }
+
+    /// Exposes a host value to scripts as a global variable.
+    pub fn register_global(&mut self, name: &str, value: Value) -> Result<&mut Self, Flow> {
+        self.env
+            .global()
+            .borrow_mut()
+            .define_value(name.to_string(), value)?;
+        Ok(self)
+    }
+
+    /// Makes a host type constructible from scripts as `TypeName { ... }`.
+    pub fn register_native_type(&mut self, native_type: NativeType) -> Result<&mut Self, Flow> {
+        self.native_types
+            .insert(native_type.name.clone(), Rc::new(native_type));
+        Ok(self)
+    }
+
+    /// Wraps host state in an instance of a registered native type, e.g. to pass it to `register_global`.
+    pub fn new_native_object<T: 'static>(&self, type_name: &str, data: T) -> Result<Value, Flow> {
+        match self.native_types.get(type_name) {
+            Some(native_type) => Ok(Value::new_native_object(NativeObject::new(
+                native_type.clone(),
+                Box::new(data),
+            ))),
+            None => Err(Flow::error(format!("Type {} not defined", type_name))),
+        }
+    }
+
+    /// Calls `name` on a script value, e.g. each entity's `update(dt)` from a host game loop.
+    pub fn call_method(
+        &mut self,
+        value: &Value,
+        name: &str,
+        arguments: &[Value],
+    ) -> Result<Value, Error> {
+        let method = self.get_method(value, name)?;
+        Ok(self.run(|interpreter| interpreter.call_value(&method, arguments))?)
+    }
+
+    /// Calls a global function, either a script-level one or one registered by the host.
+    pub fn call_global(&mut self, name: &str, arguments: &[Value]) -> Result<Value, Error> {
+        let method = self.env.global().borrow().get_method(name)?;
+        Ok(self.run(|interpreter| interpreter.call_value(&method, arguments))?)
+    }
+
+    /// Looks up a global variable or function, e.g. to fetch objects after running a script.
+    pub fn get_global(&self, name: &str) -> Result<Value, Error> {
+        let global = self.env.global();
+        let global = global.borrow();
+        match global.get_value(name) {
+            Ok(value) => Ok(value),
+            Err(_) => Ok(global.get_method(name)?),
+        }
+    }
+
     pub fn interprete(&mut self, statements: &Vec<Statement>) -> Result<Value, Flow> {
-        self.execute_statements(statements)?;
+        self.run(|interpreter| interpreter.execute_statements(statements))?;
         Ok(Value::Void)
     }
 
+    fn run<T>(&mut self, f: impl FnOnce(&mut Self) -> Result<T, Flow>) -> Result<T, Flow> {
+        let depth = self.env.depth();
+        let call_depth = self.budget.call_depth;
+        if self.budget.runs == 0 {
+            self.budget.reset();
+        }
+        self.budget.runs += 1;
+        let result = f(self);
+        self.env.truncate(depth);
+        self.budget.call_depth = call_depth;
+        let finalized = self.run_pending_deinit();
+        self.budget.runs -= 1;
+        // An interrupt is spent once it stopped a run; one that arrives between runs stops the next.
+        if let Err(Flow::Error(error)) = &result {
+            if error.kind == ErrorKind::Interrupted && self.budget.runs == 0 {
+                self.budget.interrupt.clear();
+            }
+        }
+        let value = result?;
+        finalized?;
+        Ok(value)
+    }
+
     pub fn execute_block(&mut self, block: &Vec<Statement>) -> Result<Value, Flow> {
         self.env.push_default();
         let value = self.execute_statements(block);
@@ -56,43 +301,70 @@ impl Interpreter {
 
     pub fn execute_statements(&mut self, block: &Vec<Statement>) -> Result<Value, Flow> {
         for statement in block {
-            self.execute_statement(statement)?;
+            let result = self.execute_statement(statement).map(|_| ());
+            if gc::should_collect() {
+                gc::collect();
+            }
+            let finalized = self.run_pending_deinit();
+            result?;
+            finalized?;
         }
         Ok(Value::Void)
     }
 
+    /// Runs `deinit` for instances dropped since the last statement; the first error wins.
+    pub fn run_pending_deinit(&mut self) -> Result<(), Flow> {
+        let mut result = Ok(());
+        while let Some(object) = self.pending_deinit.pop() {
+            let object = Rc::new(RefCell::new(object));
+            let finalized = Object::get_bound_method(&object, "deinit")
+                .and_then(|deinit| self.call_value(&deinit, &[]));
+            if result.is_ok() {
+                result = finalized.map(|_| ());
+            }
+        }
+        result
+    }
+
     pub fn execute_statement(&mut self, statement: &Statement) -> Result<Value, Flow> {
-        match statement {
-            Statement::Object {
+        self.budget.step()?;
+        match &statement.node {
+            StatementKind::Object {
                 name,
                 type_annotation,
                 methods,
             } => self.execute_object(name, type_annotation, methods),
-            Statement::Trait {
+            StatementKind::Trait {
                 name,
                 type_annotation,
                 method_signatures,
             } => self.execute_trait(name, type_annotation, method_signatures),
-            Statement::Var {
+            StatementKind::Var {
                 name,
                 type_annotation,
                 initializer,
             } => self.execute_var(name, type_annotation, initializer),
-            Statement::While { condition, body } => self.execute_while(condition, body),
-            Statement::For {
+            StatementKind::While { condition, body } => self.execute_while(condition, body),
+            StatementKind::For {
                 variable,
                 iterator,
                 body,
             } => self.execute_for(variable, iterator, body),
-            Statement::If {
+            StatementKind::If {
                 condition,
                 then_branch,
                 else_branch,
             } => self.execute_if(condition, then_branch, else_branch),
-            Statement::Break => Err(Flow::Break),
-            Statement::Continue => Err(Flow::Continue),
-            Statement::Return(expression) => self.execute_return(expression),
-            Statement::Expression(expression) => self.evaluate_expression(expression),
+            StatementKind::Break => Err(Flow::Break),
+            StatementKind::Continue => Err(Flow::Continue),
+            StatementKind::Return(expression) => self.execute_return(expression),
+            StatementKind::Throw(expression) => self.execute_throw(expression),
+            StatementKind::Try {
+                body,
+                catch,
+                finally,
+            } => self.execute_try(body, catch, finally),
+            StatementKind::Expression(expression) => self.evaluate_expression(expression),
         }
     }
 
@@ -104,12 +376,46 @@ impl Interpreter {
     ) -> Result<Value, Flow> {
         let mut object = Object::new();
         for method_decl in methods {
-            let name = method_decl.signature.name.clone();
-            let method = Method::new(method_decl.clone());
-            object.define_method(name, Value::new_method(method))?;
+            let method_name = method_decl.signature.name.clone();
+            if object.methods.contains_key(&method_name) {
+                return Err(Flow::error(format!(
+                    "Duplicate method {} in object {}",
+                    method_name, name
+                )));
+            }
+            let method = Method::new(Rc::new(method_decl.clone()));
+            object.define_method(method_name, Value::new_method(method))?;
         }
-        self.prototypes.insert(name.clone(), object);
+        self.prototypes.insert(name.clone(), Rc::new(object));
+
+        Ok(Value::Void)
+    }
+
+    fn check_traits(
+        &self,
+        type_name: &str,
+        traits: &[String],
+        arity_of: impl Fn(&str) -> Option<(usize, usize)>,
+    ) -> Result<Value, Flow> {
+        for trait_name in traits {
+            let signatures = match self.traits.get(trait_name) {
+                Some(signatures) => signatures,
+                None => return Err(Flow::error(format!("Trait {} not defined", trait_name))),
+            };
 
+            for signature in signatures {
+                let arity = signature.params.len();
+                match arity_of(&signature.name) {
+                    Some((min_arity, max_arity)) if min_arity <= arity && arity <= max_arity => {}
+                    _ => {
+                        return Err(Flow::error(format!(
+                            "{} does not implement {}({} arguments) of trait {}",
+                            type_name, signature.name, arity, trait_name
+                        )))
+                    }
+                }
+            }
+        }
         Ok(Value::Void)
     }
 
@@ -119,25 +425,32 @@ impl Interpreter {
         type_annotation: &Option<Vec<String>>,
         method_signatures: &Vec<MethodSignature>,
     ) -> Result<Value, Flow> {
-        // Do nothing for now
+        for (i, signature) in method_signatures.iter().enumerate() {
+            if method_signatures[..i]
+                .iter()
+                .any(|other| other.name == signature.name)
+            {
+                return Err(Flow::error(format!(
+                    "Duplicate method {} in trait {}",
+                    signature.name, name
+                )));
+            }
+        }
+        self.traits.insert(name.clone(), method_signatures.clone());
         Ok(Value::Void)
     }
 
     pub fn execute_var(
         &mut self,
-        name: &String,
+        name: &Symbol,
         type_annotation: &Option<Vec<String>>,
         initializer: &Expression,
     ) -> Result<Value, Flow> {
         let value = self.evaluate_expression(initializer)?;
-        self.env.define_value(name.to_string(), value)
+        self.env.define_value(name.clone(), value)
     }
 
-    pub fn execute_while(
-        &mut self,
-        condition: &Expression,
-        body: &Vec<Statement>,
-    ) -> Result<Value, Flow> {
+    pub fn execute_while(&mut self, condition: &Expression, body: &Block) -> Result<Value, Flow> {
         while self.evaluate_expression(condition)?.as_bool()? {
             let returns = self.execute_block(body);
             if let Err(flow) = &returns {
@@ -153,15 +466,15 @@ impl Interpreter {
 
     pub fn execute_for(
         &mut self,
-        variable: &String,
+        variable: &Symbol,
         iterator: &Expression,
-        body: &Vec<Statement>,
+        body: &Block,
     ) -> Result<Value, Flow> {
         let value = self.evaluate_expression(iterator)?;
-        let iterator = value.as_array()?;
-        for value in iterator.borrow().elements.iter() {
+        let elements = value.as_array()?.borrow().elements.clone();
+        for value in elements.iter() {
             self.env.push_default();
-            self.env.define_value(variable.to_string(), value.clone())?;
+            self.env.define_value(variable.clone(), value.clone())?;
             let returns = self.execute_statements(body);
             self.env.pop()?;
 
@@ -179,8 +492,8 @@ impl Interpreter {
     pub fn execute_if(
         &mut self,
         condition: &Expression,
-        then_branch: &Vec<Statement>,
-        else_branch: &Option<Vec<Statement>>,
+        then_branch: &Block,
+        else_branch: &Option<Block>,
     ) -> Result<Value, Flow> {
         if self.evaluate_expression(condition)?.as_bool()? {
             self.execute_block(then_branch)?;
@@ -197,9 +510,52 @@ impl Interpreter {
         }
     }
 
+    pub fn execute_throw(&mut self, expression: &Expression) -> Result<Value, Flow> {
+        let value = self.evaluate_expression(expression)?;
+        Err(Flow::Error(Box::new(Error::thrown(value))))
+    }
+
+    pub fn execute_try(
+        &mut self,
+        body: &Block,
+        catch: &Option<(Symbol, Block)>,
+        finally: &Option<Block>,
+    ) -> Result<Value, Flow> {
+        let depth = self.env.depth();
+        let call_depth = self.budget.call_depth;
+
+        let mut result = self.execute_block(body);
+        if let (Err(Flow::Error(error)), Some((variable, catch_body))) = (&result, catch) {
+            if error.is_catchable() {
+                self.env.truncate(depth);
+                self.budget.call_depth = call_depth;
+
+                let value = error.to_value()?;
+                self.env.push_default();
+                let caught = self
+                    .env
+                    .define_value(variable.clone(), value)
+                    .and_then(|_| self.execute_statements(catch_body));
+                self.env.pop()?;
+                result = caught;
+            }
+        }
+
+        if let Some(finally) = finally {
+            self.env.truncate(depth);
+            self.budget.call_depth = call_depth;
+            self.execute_block(finally)?;
+        }
+
+        result
+    }
+
     pub fn evaluate_expression(&mut self, expression: &Expression) -> Result<Value, Flow> {
+        self.budget.step()?;
         match expression {
-            Expression::Call { callee, arguments } => self.evaluate_call(callee, arguments),
+            Expression::Call {
+                callee, arguments, ..
+            } => self.evaluate_call(callee, arguments),
             Expression::Binary {
                 left,
                 operator,
@@ -211,16 +567,20 @@ impl Interpreter {
             }
             Expression::IndexAccess { object, index } => self.evaluate_index_access(object, index),
             Expression::Assignment { target, value } => self.evaluate_assignment(target, value),
-            Expression::ObjectConstruction { type_name, fields } => {
-                self.evaluate_object_construction(type_name, fields)
-            }
-            Expression::ArrayConstruction { elements } => {
+            Expression::ObjectConstruction {
+                type_name, fields, ..
+            } => self.evaluate_object_construction(type_name, fields),
+            Expression::ArrayConstruction { elements, .. } => {
                 self.evaluate_array_construction(elements)
             }
             Expression::CallableIdentifier(name) => self.evaluate_callable_identifier(name),
             Expression::Identifier(name) => self.evaluate_identifier(name),
-            Expression::NumberLiteral(n) => Ok(Value::Number(n.parse().unwrap())),
-            Expression::StringLiteral(s) => Ok(Value::new_string(s.clone())),
+            Expression::IntegerLiteral(n, _) => Ok(Value::Integer(*n)),
+            Expression::NumberLiteral(n, _) => Ok(Value::Number(*n)),
+            Expression::StringLiteral(s) => {
+                self.budget.allocate(s.len())?;
+                Ok(Value::String(Str::from(s.clone())))
+            }
             Expression::BoolLiteral(b) => Ok(Value::Bool(*b)),
             Expression::Null => Ok(Value::Null),
         }
@@ -229,7 +589,7 @@ impl Interpreter {
     fn evaluate_call(
         &mut self,
         callee: &Expression,
-        arguments: &Vec<Expression>,
+        arguments: &[Spanned<Expression>],
     ) -> Result<Value, Flow> {
         let value = self.evaluate_expression(callee)?;
 
@@ -239,11 +599,36 @@ impl Interpreter {
             args.push(arg);
         }
 
+        self.call_value(&value, &args)
+    }
+
+    pub fn call_value(&mut self, callee: &Value, arguments: &[Value]) -> Result<Value, Flow> {
+        match callee {
+            Value::Method(method) => method.borrow().call(self, arguments),
+            Value::NativeFunction(native_function) => {
+                let value = native_function.borrow().call(self, arguments)?;
+                self.budget.allocate_returned(&value)?;
+                Ok(value)
+            }
+            Value::NativeMethod(native_method) => {
+                let value = native_method.borrow().call(self, arguments)?;
+                self.budget.allocate_returned(&value)?;
+                Ok(value)
+            }
+            _ => Err(Flow::error("Invalid call".to_string())),
+        }
+    }
+
+    pub fn get_method(&self, value: &Value, name: &str) -> Result<Value, Flow> {
         match value {
-            Value::Method(method) => method.borrow().call(self, &args),
-            Value::NativeFunction(native_method) => native_method.borrow().call(self, &args),
-            Value::NativeMethod(native_method) => native_method.borrow().call(self, &args),
-            _ => Err(Flow::Error("Invalid call".to_string())),
+            Value::Object(object) => Object::get_bound_method(object, name),
+            Value::Array(array) => Array::get_method(array, name),
+            Value::NativeObject(object) => NativeObject::get_method(object, name),
+            _ => Err(Flow::error(format!(
+                "Cannot call method {} on {}",
+                name,
+                value.type_name()
+            ))),
         }
     }
 
@@ -256,7 +641,7 @@ impl Interpreter {
         let left = self.evaluate_expression(left)?;
         let right = &self.evaluate_expression(right)?;
 
-        match operator {
+        let value = match operator {
             BinaryOp::Add => left.add(right),
             BinaryOp::Subtract => left.sub(right),
             BinaryOp::Multiply => left.mul(right),
@@ -270,7 +655,20 @@ impl Interpreter {
             BinaryOp::LessEqual => left.le(right),
             BinaryOp::And => left.and(right),
             BinaryOp::Or => left.or(right),
+            BinaryOp::BitAnd => left.bit_and(right),
+            BinaryOp::BitOr => left.bit_or(right),
+            BinaryOp::BitXor => left.bit_xor(right),
+            BinaryOp::ShiftLeft => left.shl(right),
+            BinaryOp::ShiftRight => left.shr(right),
+        }?;
+        match (&left, &value) {
+            // Appending in place only allocates the new suffix.
+            (Value::String(left), Value::String(string)) if string.extends(left) => {
+                self.budget.allocate(string.len() - left.len())?
+            }
+            _ => self.budget.allocate_value(&value)?,
         }
+        Ok(value)
     }
 
     fn evaluate_unary(&mut self, operator: &UnaryOp, operand: &Expression) -> Result<Value, Flow> {
@@ -279,6 +677,7 @@ impl Interpreter {
         match operator {
             UnaryOp::Negate => operand.neg(),
             UnaryOp::Not => operand.not(),
+            UnaryOp::BitNot => operand.bit_not(),
         }
     }
 
@@ -295,17 +694,25 @@ impl Interpreter {
             }
             Value::Object(object) => {
                 self.env.push(object.clone());
-                value = self.evaluate_expression(identifier)?;
+                let result = self.evaluate_expression(identifier);
                 self.env.pop()?;
+                value = result?;
             }
             Value::Array(array) => {
-                self.env
-                    .push(array.borrow().object_wrapper.clone().unwrap());
-                value = self.evaluate_expression(identifier)?;
-                self.env.pop()?;
+                value = match identifier {
+                    Expression::CallableIdentifier(name) => Array::get_method(array, name)?,
+                    _ => return Err(Flow::error("Invalid dot access".to_string())),
+                };
+            }
+            Value::NativeObject(object) => {
+                value = match identifier {
+                    Expression::CallableIdentifier(name) => NativeObject::get_method(object, name)?,
+                    Expression::Identifier(name) => object.borrow().get_value(name)?,
+                    _ => return Err(Flow::error("Invalid dot access".to_string())),
+                };
             }
             _ => {
-                return Err(Flow::Error("Invalid dot access".to_string()));
+                return Err(Flow::error("Invalid dot access".to_string()));
             }
         }
 
@@ -321,16 +728,24 @@ impl Interpreter {
 
         match &value {
             Value::String(string) => {
-                let index = self.evaluate_expression(index)?.as_number()?;
-                let value = string.borrow().chars().nth(index as usize).unwrap();
-                Ok(Value::new_string(value.to_string()))
+                let index = self.evaluate_expression(index)?.as_integer()?;
+                if index < 0 {
+                    return Err(Flow::error("Index out of bounds".to_string()));
+                }
+                match string.as_str().chars().nth(index as usize) {
+                    Some(value) => {
+                        self.budget.allocate(value.len_utf8())?;
+                        Ok(Value::new_string(value.to_string()))
+                    }
+                    None => Err(Flow::error("Index out of bounds".to_string())),
+                }
             }
             Value::Array(array) => {
-                let index = self.evaluate_expression(index)?.as_number()?;
-                let value = array.borrow().get_value(index as i32);
+                let index = self.evaluate_expression(index)?.as_integer()?;
+                let value = array.borrow().get_value(index);
                 value
             }
-            _ => Err(Flow::Error("Invalid index access".to_string())),
+            _ => Err(Flow::error("Invalid index access".to_string())),
         }
     }
 
@@ -342,12 +757,12 @@ impl Interpreter {
         let value = self.evaluate_expression(value)?;
 
         if value.is_void() {
-            return Err(Flow::Error("Cannot assign void".to_string()));
+            return Err(Flow::error("Cannot assign void".to_string()));
         }
 
         match target {
             Expression::Identifier(name) => {
-                self.env.set_value(name.to_string(), value.clone())?;
+                self.env.set_value(name, value.clone())?;
                 return Ok(Value::Void);
             }
             Expression::IndexAccess { object, index } => {
@@ -356,46 +771,55 @@ impl Interpreter {
 
                 match &object {
                     Value::Array(array) => {
-                        let index = index.as_number()?;
-                        array.borrow_mut().set_value(index as i32, value)?;
+                        let index = index.as_integer()?;
+                        array.borrow_mut().set_value(index, value)?;
                         return Ok(Value::Void);
                     }
                     _ => {}
                 }
             }
+            Expression::DotAccess { object, identifier } => {
+                let object = self.evaluate_expression(object)?;
+                let name = match identifier.as_ref() {
+                    Expression::Identifier(name) => name,
+                    _ => return Err(Flow::error("Invalid assignment target".to_string())),
+                };
+
+                if let Value::NativeObject(object) = &object {
+                    object.borrow_mut().set_value(name, value)?;
+                    return Ok(Value::Void);
+                }
+            }
             _ => {}
         }
 
-        Err(Flow::Error("Invalid assignment target".to_string()))
+        Err(Flow::error("Invalid assignment target".to_string()))
     }
 
     fn evaluate_object_construction(
         &mut self,
         type_name: &Option<String>,
-        fields: &HashMap<String, Expression>,
+        fields: &[(Symbol, Spanned<Expression>)],
     ) -> Result<Value, Flow> {
         if let Some(type_name) = type_name {
-            return match self.prototypes.get(type_name) {
-                Some(object) => {
-                    let object = Rc::new(RefCell::new(object.instantiate()));
+            return match self.prototypes.get(type_name).cloned() {
+                Some(prototype) => {
+                    let object = Rc::new(RefCell::new(Object::instantiate(&prototype)));
+                    gc::track_object(&object);
                     let mut init_args = Vec::new();
 
-                    for method in object.borrow_mut().methods.values_mut() {
-                        let method = method.as_method()?;
-                        method.borrow_mut().bind(object.clone());
-                    }
-
                     for (name, value) in fields {
                         let value = self.evaluate_expression(value)?;
                         object.borrow_mut().define_value(name.clone(), value)?;
                     }
 
-                    if let Ok(init_method) = object.borrow().get_method("init") {
+                    let init_method = Object::get_bound_method(&object, "init");
+                    if let Ok(init_method) = init_method {
                         let init_method = init_method.as_method()?;
                         let init_method_params = &init_method.borrow().declaration.signature.params;
 
                         if fields.len() != init_method_params.len() {
-                            return Err(Flow::Error(format!(
+                            return Err(Flow::error(format!(
                                 "Invalid number of arguments for init method: expected {}, got {}",
                                 init_method_params.len(),
                                 fields.len()
@@ -411,16 +835,22 @@ impl Interpreter {
                         init_method.borrow().call(self, &init_args)?;
                     } else {
                         if fields.len() > 0 {
-                            return Err(Flow::Error(format!(
+                            return Err(Flow::error(format!(
                                 "Unexpected arguments for object of type {}",
                                 type_name
                             )));
                         }
                     }
 
-                    Ok(Value::Object(object))
+                    if prototype.methods.contains_key("deinit") {
+                        object.borrow_mut().deinit = Some(self.pending_deinit.clone());
+                    }
+
+                    let value = Value::Object(object);
+                    self.budget.allocate_value(&value)?;
+                    Ok(value)
                 }
-                None => Err(Flow::Error(format!("Type {} not defined", type_name))),
+                None => self.construct_native_object(type_name, fields),
             };
         } else {
             let mut object = Object::new();
@@ -428,50 +858,108 @@ impl Interpreter {
                 let value = self.evaluate_expression(value)?;
                 object.define_value(name.clone(), value)?;
             }
-            Ok(Value::new_object(object))
+            let value = Value::new_object(object);
+            self.budget.allocate_value(&value)?;
+            Ok(value)
         }
     }
 
-    fn evaluate_array_construction(&mut self, elements: &Vec<Expression>) -> Result<Value, Flow> {
+    fn construct_native_object(
+        &mut self,
+        type_name: &str,
+        fields: &[(Symbol, Spanned<Expression>)],
+    ) -> Result<Value, Flow> {
+        let native_type = match self.native_types.get(type_name) {
+            Some(native_type) => native_type.clone(),
+            None => return Err(Flow::error(format!("Type {} not defined", type_name))),
+        };
+
+        self.check_traits(type_name, &native_type.traits, |method_name| {
+            native_type
+                .methods
+                .get(method_name)
+                .map(|method| (method.min_arity, method.max_arity))
+        })?;
+
+        let mut values = HashMap::new();
+        for (name, value) in fields {
+            values.insert(name.to_string(), self.evaluate_expression(value)?);
+        }
+
+        let data = (native_type.constructor)(self, &values)?;
+        Ok(Value::new_native_object(NativeObject::new(
+            native_type,
+            data,
+        )))
+    }
+
+    fn evaluate_array_construction(
+        &mut self,
+        elements: &[Spanned<Expression>],
+    ) -> Result<Value, Flow> {
         let mut array_elements = Vec::new();
         for element in elements {
             let value = self.evaluate_expression(element)?;
             array_elements.push(value);
         }
 
-        Value::new_array(array_elements)
+        let value = Value::new_array(array_elements)?;
+        self.budget.allocate_value(&value)?;
+        Ok(value)
     }
 
-    fn evaluate_identifier(&mut self, name: &String) -> Result<Value, Flow> {
+    fn evaluate_identifier(&mut self, name: &str) -> Result<Value, Flow> {
         self.env.get_value(name)
     }
 
-    fn evaluate_callable_identifier(&mut self, name: &String) -> Result<Value, Flow> {
+    fn evaluate_callable_identifier(&mut self, name: &str) -> Result<Value, Flow> {
         self.env.get_method(name)
     }
 }
 
+impl Drop for Interpreter {
+    fn drop(&mut self) {
+        // Release globals first so instances they hold still get their `deinit`, then the
+        // namespaces in the prelude, which scripts can store instances in too. The prelude's
+        // functions stay so the last `deinit`s can still call them.
+        self.env.truncate(BASE_DEPTH);
+        let globals = mem::take(&mut self.env.global().borrow_mut().values);
+        drop(globals);
+        gc::collect();
+        let _ = self.run_pending_deinit();
+        let namespaces = mem::take(&mut self.env.prelude().borrow_mut().values);
+        drop(namespaces);
+        gc::collect();
+        let _ = self.run_pending_deinit();
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use crate::{lexer::Lexer, parser::Parser};
 
+    use super::super::{symbol::intern, time::ManualClock};
     use super::*;
 
     fn eval(source: &str) {
         let tokens = Lexer::new(source).lex();
-        let statements = Parser::new(tokens).parse();
+        let statements = Parser::new(tokens).parse().unwrap();
         let result = Interpreter::new()
             .with_std()
             .unwrap()
             .interprete(&statements);
 
-        if let Err(flow) = &result {
-            if let Flow::Error(msg) = flow {
-                panic!("{}", msg);
-            }
+        if let Err(Flow::Error(error)) = &result {
+            panic!("{}", error);
         }
     }
 
+    fn eval_with(interpreter: &mut Interpreter, source: &str) -> Result<Value, Flow> {
+        let tokens = Lexer::new(source).lex();
+        let statements = Parser::new(tokens).parse().unwrap();
+        interpreter.interprete(&statements)
+    }
+
     #[test]
     fn test_object_field() {
         eval(
@@ -506,6 +994,382 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_integers() {
+        eval(
+            r#"
+            assert(7 / 2, 3);
+            assert(-7 / 2, -3);
+            assert(7 % 3, 1);
+            assert(7.0 / 2, 3.5);
+            assert(1 + 0.5, 1.5);
+            assert(2 == 2.0, true);
+            assert(str(2), "2");
+            assert(str(2.0), "2.0");
+            assert(str(1 + 1.0), "2.0");
+            assert(range(0, 3), [0, 1, 2]);
+            assert(wrappingAdd(9223372036854775807, 1), -9223372036854775807 - 1);
+            assert(wrappingMul(4611686018427387904, 2), -9223372036854775807 - 1);
+
+            var array = [1, 2, 3];
+            assert(array[2.0], 3);
+            "#,
+        );
+
+        let mut interpreter = Interpreter::new();
+        interpreter.with_std().unwrap();
+        let error = |interpreter: &mut Interpreter, source| {
+            Error::from(eval_with(interpreter, source).unwrap_err()).message
+        };
+        assert_eq!(
+            error(&mut interpreter, "9223372036854775807 + 1;"),
+            "Integer overflow"
+        );
+        assert_eq!(error(&mut interpreter, "1 / 0;"), "Division by zero");
+        assert_eq!(error(&mut interpreter, "1 % 0;"), "Division by zero");
+        assert_eq!(
+            error(&mut interpreter, "[1, 2][0.5];"),
+            "Expected integer but got 0.5"
+        );
+        assert_eq!(
+            error(&mut interpreter, "\"ab\"[1.5];"),
+            "Expected integer but got 1.5"
+        );
+    }
+
+    #[test]
+    fn test_bitwise_operators() {
+        eval(
+            r#"
+            assert(12 & 10, 8);
+            assert(12 | 10, 14);
+            assert(12 ^ 10, 6);
+            assert(~0, -1);
+            assert(1 << 4, 16);
+            assert(-16 >> 2, -4);
+
+            var board = 0;
+            for (var cell in [0, 3, 5]) { board = board | (1 << cell); }
+            assert(board, 41);
+            assert((board >> 3) & 1, 1);
+            "#,
+        );
+
+        let mut interpreter = Interpreter::new();
+        interpreter.with_std().unwrap();
+        let error = Error::from(eval_with(&mut interpreter, "1 << 64;").unwrap_err());
+        assert_eq!(error.message, "Shift amount 64 out of range");
+        let error = Error::from(eval_with(&mut interpreter, "1.0 & 1;").unwrap_err());
+        assert_eq!(error.message, "Invalid operands for bitwise and operation");
+    }
+
+    #[test]
+    fn test_math() {
+        eval(
+            r#"
+            assert(math.sqrt(16), 4.0);
+            assert(math.pow(2, 10), 1024.0);
+            assert(math.floor(-1.5), -2.0);
+            assert(math.atan2(1, 1), math.PI / 4);
+            assert(math.abs(-3), 3);
+            assert(math.abs(-2.5), 2.5);
+            assert(str(math.min(3, 1, 2)), "1");
+            assert(str(math.max(1, 2.5)), "2.5");
+            assert(str(math.max(1, 2.0)), "2.0");
+            assert(math.clamp(15, 0, 10), 10);
+            assert(math.clamp(-0.5, 0.0, 1.0), 0.0);
+            assert(math.lerp(0, 10, 0.25), 2.5);
+            assert(math.toInt(-2.7), -2);
+            assert(math.gcd(12, -18), 6);
+            assert(math.sign(-4), -1);
+            assert(math.MAX_INT, 9223372036854775807);
+            "#,
+        );
+
+        let mut interpreter = Interpreter::new();
+        interpreter.with_std().unwrap();
+        let error = |interpreter: &mut Interpreter, source| {
+            Error::from(eval_with(interpreter, source).unwrap_err()).message
+        };
+        assert_eq!(
+            error(&mut interpreter, "math.sqrt(1, 2);"),
+            "Expected 1 arguments but got 2"
+        );
+        assert_eq!(
+            error(&mut interpreter, "math.sqrt(\"4\");"),
+            "Argument 1: Expected number but got string"
+        );
+        assert_eq!(
+            error(&mut interpreter, "math.clamp(1, 10, 0);"),
+            "Invalid clamp range 10 to 0"
+        );
+        assert_eq!(
+            error(&mut interpreter, "math.abs(math.MIN_INT);"),
+            "Integer overflow"
+        );
+    }
+
+    #[test]
+    fn test_random() {
+        let mut interpreter = Interpreter::new();
+        interpreter.with_std().unwrap().with_seed(7).unwrap();
+        eval_with(
+            &mut interpreter,
+            r#"
+            var random = Random { seed = 42 };
+            var rolls = [];
+            for (var i in range(0, 5)) { rolls.add(random.nextInt(0, 100)); }
+            assert(rolls, [8, 37, 68, 92, 99]);
+
+            random.seed(42);
+            assert(random.nextInt(0, 100), 8);
+            var x = random.nextFloat();
+            assert(x >= 0.0 && x < 1.0, true);
+
+            var cards = [1, 2, 3, 4, 5, 6];
+            random.shuffle(cards);
+            assert(cards.length(), 6);
+            var total = 0;
+            for (var card in cards) { total = total + card; }
+            assert(total, 21);
+            assert(random.choice(["only"]), "only");
+
+            var first = Random {}.nextInt(0, 1000000);
+            "#,
+        )
+        .unwrap();
+
+        // The default generator, and generators seeded from it, repeat under the same seed.
+        let first = interpreter.get_global("first").unwrap();
+        let mut interpreter = Interpreter::new();
+        interpreter.with_std().unwrap().with_seed(7).unwrap();
+        eval_with(
+            &mut interpreter,
+            "var first = Random {}.nextInt(0, 1000000);",
+        )
+        .unwrap();
+        assert_eq!(interpreter.get_global("first").unwrap(), first);
+
+        let error = Error::from(eval_with(&mut interpreter, "random.nextInt(5, 5);").unwrap_err());
+        assert_eq!(error.message, "Invalid range 5 to 5");
+        let error = Error::from(eval_with(&mut interpreter, "random.choice([]);").unwrap_err());
+        assert_eq!(error.message, "Cannot choose from an empty array");
+    }
+
+    #[test]
+    fn test_json() {
+        let mut interpreter = Interpreter::new();
+        interpreter.with_std().unwrap();
+        let level = r#"{
+            "name": "Level \"1\"\n",
+            "size": [640, 480],
+            "gravity": -9.8e0,
+            "big": 12345678901234567890,
+            "tiles": [{"solid": true, "item": null}, {}],
+            "unicode": "\u00e9\ud83d\ude00"
+        }"#;
+        interpreter
+            .register_global("level", Value::new_string(level.to_string()))
+            .unwrap();
+        eval_with(
+            &mut interpreter,
+            r#"
+            var data = json.parse(level);
+            assert(data.size[0], 640);
+            assert(data.gravity, -9.8);
+            assert(data.big, 12345678901234567000.0);
+            assert(data.tiles[0].solid, true);
+            assert(data.tiles[0].item, null);
+            assert(data.unicode, "é😀");
+            assert(json.parse(json.stringify(data)), data);
+
+            var flat = json.stringify([1, 2.5, data.name, true, null, {}]);
+            var pretty = json.stringify({ b = [1], a = { c = 2 } }, 2);
+            "#,
+        )
+        .unwrap();
+
+        let global = |name| interpreter.get_global(name).unwrap().to_string();
+        assert_eq!(global("flat"), r#"[1,2.5,"Level \"1\"\n",true,null,{}]"#);
+        assert_eq!(
+            global("pretty"),
+            "{\n  \"a\": {\n    \"c\": 2\n  },\n  \"b\": [\n    1\n  ]\n}"
+        );
+
+        let mut error = |source: &str, json: &str| {
+            let text = Value::new_string(json.to_string());
+            interpreter
+                .env
+                .global()
+                .borrow_mut()
+                .values
+                .insert(intern("text"), text);
+            Error::from(eval_with(&mut interpreter, source).unwrap_err()).message
+        };
+        assert_eq!(
+            error("json.parse(text);", "[1, 2"),
+            "Invalid JSON: Expected ',' or ']' in array at line 1, column 6"
+        );
+        assert_eq!(
+            error("json.parse(text);", "{\n  true }"),
+            "Invalid JSON: Expected string key at line 2, column 3"
+        );
+        assert_eq!(
+            error("json.parse(text);", "[01]"),
+            "Invalid JSON: Expected ',' or ']' in array at line 1, column 3"
+        );
+        assert_eq!(
+            error("json.parse(text);", "1 2"),
+            "Invalid JSON: Unexpected trailing characters at line 1, column 3"
+        );
+        assert_eq!(
+            error("json.parse(text);", "\"\\ud83d\""),
+            "Invalid JSON: Unpaired surrogate in unicode escape at line 1, column 9"
+        );
+        assert_eq!(
+            error("var a = []; a.add(a); json.stringify(a);", ""),
+            "Cannot stringify a cyclic structure"
+        );
+        assert_eq!(
+            error("json.stringify({ generator = random });", ""),
+            "Cannot stringify a value of type Random"
+        );
+        assert_eq!(
+            error("json.stringify(math.INFINITY);", ""),
+            "Cannot stringify inf"
+        );
+
+        // Keys come from data, so they are not kept alive in the symbol table.
+        let keyed = Value::new_string(r#"{"jsonOnlyKey": 1}"#.to_string());
+        interpreter.register_global("keyed", keyed).unwrap();
+        eval_with(&mut interpreter, "var parsed = json.parse(keyed);").unwrap();
+        let parsed = interpreter
+            .get_global("parsed")
+            .unwrap()
+            .as_object()
+            .unwrap();
+        let key = parsed.borrow().values.keys().next().unwrap().clone();
+        assert!(!Rc::ptr_eq(&key, &intern("jsonOnlyKey")));
+
+        // Shared values make the output exponentially larger than the data behind it.
+        let error = limit_error(
+            Limits::default().with_max_allocated_bytes(100_000),
+            "var a = [1]; var i = 0; while (i < 30) { a = [a, a]; i = i + 1; } json.stringify(a);",
+        );
+        assert_eq!(error.kind, ErrorKind::MemoryLimit);
+    }
+
+    #[test]
+    fn test_fs() {
+        let root = std::env::temp_dir().join(format!("juice-fs-{}", std::process::id()));
+        std::fs::create_dir_all(root.join("data")).unwrap();
+        std::fs::write(root.join("data/levels.txt"), "one\ntwo\n").unwrap();
+
+        let mut interpreter = Interpreter::new();
+        interpreter.with_std().unwrap();
+        let error = eval_with(&mut interpreter, "fs.exists(\"data\");").unwrap_err();
+        assert_eq!(Error::from(error).message, "Variable fs not found");
+
+        interpreter.with_fs(&root).unwrap();
+        eval_with(
+            &mut interpreter,
+            r#"
+            assert(fs.readLines("data/levels.txt"), ["one", "two"]);
+            fs.writeText("data/save.txt", "score 10");
+            assert(fs.readText("./data/../data/save.txt"), "score 10");
+            assert(fs.listDir("data"), ["levels.txt", "save.txt"]);
+            fs.remove("data/save.txt");
+            assert(fs.exists("data/save.txt"), false);
+            assert(fs.exists("data"), true);
+            "#,
+        )
+        .unwrap();
+
+        for source in [
+            r#"fs.readText("../secret.txt");"#,
+            r#"fs.writeText("data/../../escape.txt", "");"#,
+            r#"fs.exists("/etc/passwd");"#,
+            r#"fs.remove(".");"#,
+        ] {
+            let error = Error::from(eval_with(&mut interpreter, source).unwrap_err());
+            assert_eq!(error.kind, ErrorKind::Permission, "{}", source);
+        }
+        let error = eval_with(&mut interpreter, r#"fs.readText("missing.txt");"#).unwrap_err();
+        assert_eq!(Error::from(error).kind, ErrorKind::Runtime);
+
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::symlink;
+            let outside = root.with_extension("outside");
+            std::fs::create_dir_all(&outside).unwrap();
+            symlink(outside.join("pwned.txt"), root.join("dangling")).unwrap();
+            symlink(root.join("data/levels.txt"), root.join("levels")).unwrap();
+
+            let source = r#"fs.writeText("dangling", "pwned");"#;
+            let error = Error::from(eval_with(&mut interpreter, source).unwrap_err());
+            assert_eq!(error.kind, ErrorKind::Permission);
+            assert!(!outside.join("pwned.txt").exists());
+
+            eval_with(
+                &mut interpreter,
+                r#"fs.remove("levels"); fs.remove("dangling");"#,
+            )
+            .unwrap();
+            assert!(root.join("data/levels.txt").exists());
+            assert!(std::fs::symlink_metadata(root.join("levels")).is_err());
+            assert!(std::fs::symlink_metadata(root.join("dangling")).is_err());
+            std::fs::remove_dir_all(&outside).unwrap();
+        }
+
+        std::fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn test_args_env_and_exit() {
+        let mut interpreter = Interpreter::new();
+        interpreter.with_std().unwrap();
+        for name in ["args", "env"] {
+            let error = eval_with(&mut interpreter, &format!("{};", name)).unwrap_err();
+            assert_eq!(
+                Error::from(error).message,
+                format!("Variable {} not found", name)
+            );
+        }
+
+        interpreter
+            .with_args(["level.txt", "--fast"])
+            .unwrap()
+            .with_env()
+            .unwrap();
+        eval_with(
+            &mut interpreter,
+            r#"
+            assert(args, ["level.txt", "--fast"]);
+            assert(env.get("JUICE_SURELY_UNSET"), null);
+            var path = env.get("PATH");
+            "#,
+        )
+        .unwrap();
+        assert_eq!(
+            interpreter.get_global("path").unwrap().to_string(),
+            std::env::var("PATH").unwrap()
+        );
+
+        let error = Error::from(
+            eval_with(
+                &mut interpreter,
+                "try { exit(3); } catch (e) { print(e); } finally { print(\"cleanup\"); }",
+            )
+            .unwrap_err(),
+        );
+        assert_eq!(error.kind, ErrorKind::Exit);
+        assert_eq!(error.exit_code(), Some(3));
+        let error = Error::from(eval_with(&mut interpreter, "exit();").unwrap_err());
+        assert_eq!(error.exit_code(), Some(0));
+        let error = Error::from(eval_with(&mut interpreter, "exit(1 << 40);").unwrap_err());
+        assert_eq!(error.exit_code(), None);
+    }
+
     #[test]
     fn test_method_return() {
         eval(
@@ -615,4 +1479,1065 @@ mod tests {
             "#,
         );
     }
+
+    #[test]
+    fn test_register_function_with_captured_state() {
+        let score = Rc::new(RefCell::new(0.0));
+        let mut interpreter = Interpreter::new();
+        interpreter.with_std().unwrap();
+
+        let counter = score.clone();
+        interpreter
+            .register_function("addScore", 1, move |_, arguments| {
+                *counter.borrow_mut() += arguments[0].as_number()?;
+                Ok(Value::Number(*counter.borrow()))
+            })
+            .unwrap();
+
+        eval_with(
+            &mut interpreter,
+            r#"
+            addScore(10);
+            assert(addScore(5), 15);
+            "#,
+        )
+        .unwrap();
+
+        assert_eq!(*score.borrow(), 15.0);
+    }
+
+    #[test]
+    fn test_register_function_reenters_interpreter() {
+        let mut interpreter = Interpreter::new();
+        interpreter.with_std().unwrap();
+        interpreter
+            .register_function("lookup", 1, |interpreter, arguments| {
+                let name = arguments[0].as_string()?;
+                let name = name.as_str();
+                interpreter.env.get_value(&name)
+            })
+            .unwrap();
+        interpreter
+            .register_global("width", Value::Number(640.0))
+            .unwrap();
+
+        eval_with(
+            &mut interpreter,
+            r#"
+            var height = 480;
+            assert(lookup("height"), 480);
+            assert(lookup("width"), width);
+            "#,
+        )
+        .unwrap();
+
+        assert_eq!(
+            eval_with(&mut interpreter, "lookup(1, 2);"),
+            Err(Flow::error("Expected 1 arguments but got 2".to_string()))
+        );
+    }
+
+    struct Counter {
+        count: f64,
+    }
+
+    fn counter_type() -> NativeType {
+        NativeType::new("Counter", |_, fields| {
+            let count = match fields.get("start") {
+                Some(start) => start.as_number()?,
+                None => 0.0,
+            };
+            Ok(Counter { count })
+        })
+        .with_trait("Incrementable")
+        .with_method("increment", 1, |counter: &mut Counter, _, arguments| {
+            counter.count += arguments[0].as_number()?;
+            Ok(Value::Number(counter.count))
+        })
+        .with_getter("count", |counter: &Counter| {
+            Ok(Value::Number(counter.count))
+        })
+        .with_setter("count", |counter: &mut Counter, value| {
+            counter.count = value.as_number()?;
+            Ok(Value::Void)
+        })
+        .with_display(|counter: &Counter| format!("Counter({})", counter.count))
+        .with_eq(|a: &Counter, b: &Counter| a.count == b.count)
+    }
+
+    #[test]
+    fn test_native_object() {
+        let mut interpreter = Interpreter::new();
+        interpreter.with_std().unwrap();
+        interpreter.register_native_type(counter_type()).unwrap();
+        let counter = interpreter
+            .new_native_object("Counter", Counter { count: 100.0 })
+            .unwrap();
+        interpreter
+            .register_global("shared", counter.clone())
+            .unwrap();
+
+        eval_with(
+            &mut interpreter,
+            r#"
+            trait Incrementable {
+                increment(step);
+            }
+
+            var counter = Counter { start = 5 };
+            counter.increment(2);
+            assert(counter.count, 7);
+
+            counter.count = 1;
+            assert(counter.increment(1), 2);
+            assert(str(counter), "Counter(2)");
+            assert(counter, Counter { start = 2 });
+            assert(counter == Counter {}, false);
+
+            shared.increment(1);
+            "#,
+        )
+        .unwrap();
+
+        // Only native properties can be assigned through a dot.
+        for script in ["var o = { x = 1 }; o.x = 2;", "math.PI = 3;"] {
+            assert_eq!(
+                eval_with(&mut interpreter, script),
+                Err(Flow::error("Invalid assignment target".to_string()))
+            );
+        }
+
+        let counter = counter.as_native_object().unwrap();
+        let counter = counter.borrow();
+        assert_eq!(counter.downcast_ref::<Counter>().unwrap().count, 101.0);
+    }
+
+    #[test]
+    fn test_trait_checks() {
+        let mut interpreter = Interpreter::new();
+        interpreter.with_std().unwrap();
+        interpreter.register_native_type(counter_type()).unwrap();
+
+        assert_eq!(
+            eval_with(&mut interpreter, "var counter = Counter {};"),
+            Err(Flow::error("Trait Incrementable not defined".to_string()))
+        );
+
+        assert_eq!(
+            eval_with(
+                &mut interpreter,
+                r#"
+                trait Incrementable {
+                    increment(step);
+                    reset();
+                }
+
+                var counter = Counter {};
+                "#,
+            ),
+            Err(Flow::error(
+                "Counter does not implement reset(0 arguments) of trait Incrementable".to_string()
+            ))
+        );
+
+        assert_eq!(
+            eval_with(&mut interpreter, "trait Shape { area(); area(); }"),
+            Err(Flow::error(
+                "Duplicate method area in trait Shape".to_string()
+            ))
+        );
+        assert_eq!(
+            eval_with(&mut interpreter, "object Point { move(x) { } move(y) { } }"),
+            Err(Flow::error(
+                "Duplicate method move in object Point".to_string()
+            ))
+        );
+    }
+
+    #[test]
+    fn test_register_typed_function() {
+        let mut interpreter = Interpreter::new();
+        interpreter.with_std().unwrap();
+        interpreter
+            .register_typed_function("hypot", |x: f64, y: f64| x.hypot(y))
+            .unwrap()
+            .register_typed_function("repeat", |text: String, count: i64| {
+                text.repeat(count as usize)
+            })
+            .unwrap()
+            .register_typed_function("sum", |values: Vec<f64>| values.iter().sum::<f64>())
+            .unwrap();
+
+        eval_with(
+            &mut interpreter,
+            r#"
+            assert(hypot(3, 4), 5);
+            assert(repeat("ab", 2), "abab");
+            assert(sum([1, 2, 3]), 6);
+            "#,
+        )
+        .unwrap();
+
+        assert_eq!(
+            eval_with(&mut interpreter, "hypot(3);"),
+            Err(Flow::error("Expected 2 arguments but got 1".to_string()))
+        );
+        assert_eq!(
+            eval_with(&mut interpreter, r#"repeat("ab", "2");"#),
+            Err(Flow::error(
+                "Argument 2: Expected integer but got string".to_string()
+            ))
+        );
+    }
+
+    #[test]
+    fn test_call_script_from_host() {
+        let mut interpreter = Interpreter::new();
+        interpreter.with_std().unwrap();
+        eval_with(
+            &mut interpreter,
+            r#"
+            object Player {
+                init(x) { }
+
+                update(dt) {
+                    x = x + dt;
+                    return x;
+                }
+            }
+
+            var player = Player { x = 1 };
+            var entities = [player];
+
+            object Game {
+                spawn(x) {
+                    var spawned = Player { x = x };
+                    entities.add(spawned);
+                    return spawned;
+                }
+            }
+
+            var game = Game {};
+            "#,
+        )
+        .unwrap();
+
+        let player = interpreter.get_global("player").unwrap();
+        let x = interpreter
+            .call_method(&player, "update", &[Value::Number(0.5)])
+            .unwrap();
+        assert_eq!(x, Value::Number(1.5));
+
+        let game = interpreter.get_global("game").unwrap();
+        interpreter
+            .call_method(&game, "spawn", &[Value::Number(10.0)])
+            .unwrap();
+        let entities = interpreter.get_global("entities").unwrap();
+        let length = interpreter.call_method(&entities, "length", &[]).unwrap();
+        assert_eq!(length, Value::Number(2.0));
+
+        let error = interpreter
+            .call_method(&player, "update", &[Value::Bool(true)])
+            .unwrap_err();
+        assert_eq!(error.kind, ErrorKind::Runtime);
+        assert_eq!(error.message, "Invalid operands for add operation");
+
+        let error = interpreter.call_method(&player, "render", &[]).unwrap_err();
+        assert_eq!(error.message, "Method render not found");
+        assert!(interpreter.get_global("missing").is_err());
+
+        eval_with(&mut interpreter, "var after = player.update(1);").unwrap();
+        assert_eq!(interpreter.get_global("after").unwrap(), Value::Number(2.5));
+    }
+
+    #[test]
+    fn test_call_global() {
+        let mut interpreter = Interpreter::new();
+        interpreter.with_std().unwrap();
+
+        let text = interpreter
+            .call_global("str", &[Value::Integer(4)])
+            .unwrap();
+        assert_eq!(text, Value::new_string("4".to_string()));
+
+        let error = interpreter.call_global("missing", &[]).unwrap_err();
+        assert_eq!(error.message, "Method missing not found");
+    }
+
+    #[test]
+    fn test_capture_output() {
+        let mut interpreter = Interpreter::new();
+        interpreter.with_std().unwrap();
+        let output = interpreter.capture_output();
+
+        eval_with(
+            &mut interpreter,
+            r#"
+            print("render text:", "Hello", 5);
+            print([1, 2], true);
+            "#,
+        )
+        .unwrap();
+
+        assert_eq!(output.contents(), "render text: Hello 5\n[1, 2] true\n");
+    }
+
+    #[test]
+    fn test_try_catch() {
+        let mut interpreter = Interpreter::new();
+        interpreter.with_std().unwrap();
+        let output = interpreter.capture_output();
+
+        eval_with(
+            &mut interpreter,
+            r#"
+            try {
+                print(1 / 0);
+            } catch (e) {
+                print(e.message, e.kind, e.stack.length());
+            }
+
+            object Inventory {
+                init(items) {}
+                get(i) { return this.items[i]; }
+                first() { return this.get(0); }
+            }
+            try {
+                Inventory { items = [] }.first();
+            } catch (e) {
+                print(e.message, e.stack);
+            }
+
+            try {
+                throw "not found";
+            } catch (e) {
+                print("caught", e);
+            } finally {
+                print("finally");
+            }
+
+            object Parser {
+                parse() {
+                    try {
+                        return 1;
+                    } finally {
+                        print("cleanup");
+                    }
+                }
+            }
+            print(Parser {}.parse());
+
+            for (var i in [1, 2, 3]) {
+                try {
+                    if (i == 2) { continue; }
+                    print(i);
+                } finally {
+                    print("next");
+                }
+            }
+            "#,
+        )
+        .unwrap();
+
+        assert_eq!(
+            output.contents(),
+            "Division by zero Runtime 0\n\
+             Index out of bounds [get, first]\n\
+             caught not found\n\
+             finally\n\
+             cleanup\n\
+             1\n\
+             1\n\
+             next\n\
+             next\n\
+             3\n\
+             next\n"
+        );
+    }
+
+    #[test]
+    fn test_uncaught_throw() {
+        let mut interpreter = Interpreter::new();
+        interpreter.with_std().unwrap();
+        interpreter.capture_output();
+
+        let error: Error = eval_with(
+            &mut interpreter,
+            r#"
+            try {
+                throw { message = "bad input", code = 3 };
+            } catch (e) {
+                throw e;
+            }
+            "#,
+        )
+        .unwrap_err()
+        .into();
+        assert_eq!(error.kind, ErrorKind::Thrown);
+        assert_eq!(error.message, "bad input");
+
+        let error: Error = eval_with(&mut interpreter, "try { 1 / 0; } finally { print(1); }")
+            .unwrap_err()
+            .into();
+        assert_eq!(error.kind, ErrorKind::Runtime);
+        assert_eq!(error.message, "Division by zero");
+    }
+
+    #[test]
+    fn test_deinit() {
+        let mut interpreter = Interpreter::new();
+        interpreter.with_std().unwrap();
+        let output = interpreter.capture_output();
+
+        eval_with(
+            &mut interpreter,
+            r#"
+            object Resource {
+                init(name) {}
+                deinit() { print("close", this.name); }
+            }
+
+            var a = Resource { name = "a" };
+            if (true) {
+                var b = Resource { name = "b" };
+            }
+            print("after block");
+            Resource { name = "temporary" };
+            a = null;
+
+            object Bad {
+                deinit() { throw "deinit failed"; }
+            }
+            try {
+                var bad = Bad {};
+                bad = null;
+                print("unreachable");
+            } catch (e) {
+                print("caught", e);
+            }
+
+            var saved = null;
+            object Phoenix {
+                deinit() {
+                    print("resurrect");
+                    saved = this;
+                }
+            }
+            var phoenix = Phoenix {};
+            phoenix = null;
+            saved = null;
+
+            var g = Resource { name = "global" };
+            var p = Resource { name = "prelude" };
+            print("end");
+            "#,
+        )
+        .unwrap();
+        // Hosts can keep instances in the prelude too, e.g. in a namespace they registered.
+        let resource = interpreter
+            .env
+            .global()
+            .borrow_mut()
+            .values
+            .remove("p")
+            .unwrap();
+        interpreter
+            .env
+            .prelude()
+            .borrow_mut()
+            .define_value("resource", resource)
+            .unwrap();
+        drop(interpreter);
+
+        assert_eq!(
+            output.contents(),
+            "close b\n\
+             after block\n\
+             close temporary\n\
+             close a\n\
+             caught deinit failed\n\
+             resurrect\n\
+             end\n\
+             close global\n\
+             close prelude\n"
+        );
+
+        // Each interpreter finalizes only the instances it created.
+        let mut owner = Interpreter::new();
+        owner.with_std().unwrap();
+        let owner_output = owner.capture_output();
+        let mut other = Interpreter::new();
+        other.with_std().unwrap();
+        let other_output = other.capture_output();
+
+        let source = r#"object R { deinit() { print("deinit"); } } var r = R {};"#;
+        eval_with(&mut owner, source).unwrap();
+        let resource = owner.env.get_value("r").unwrap();
+        eval_with(&mut owner, "r = null;").unwrap();
+        drop(resource);
+        eval_with(&mut other, "print(\"other\");").unwrap();
+        assert_eq!(other_output.contents(), "other\n");
+        assert_eq!(owner_output.contents(), "");
+        eval_with(&mut owner, "print(\"owner\");").unwrap();
+        assert_eq!(owner_output.contents(), "owner\ndeinit\n");
+    }
+
+    #[test]
+    fn test_collect_cycles() {
+        let mut interpreter = Interpreter::new();
+        interpreter.with_std().unwrap();
+        let output = interpreter.capture_output();
+
+        eval_with(
+            &mut interpreter,
+            r#"
+            object Text {
+                init(text) {}
+                render() { return this.text; }
+            }
+            var before = memoryStats().objects;
+            var i = 0;
+            while (i < 100) {
+                Text { text = "hello" }.render();
+                i = i + 1;
+            }
+            print("instances", memoryStats().objects - before);
+
+            object Node {
+                init(name, links) {}
+                deinit() { print("deinit", this.name); }
+            }
+            var stats = memoryStats();
+            i = 0;
+            while (i < 100) {
+                var node = { value = i, links = [] };
+                node.links.add(node);
+                var list = [node];
+                list.add(list);
+                i = i + 1;
+            }
+            var a = Node { name = "a", links = [] };
+            var b = Node { name = "b", links = [a] };
+            a.links.add(b);
+            a = null;
+            b = null;
+            print("freed", gc());
+            var after = memoryStats();
+            print("left", after.objects - stats.objects, after.arrays - stats.arrays);
+            "#,
+        )
+        .unwrap();
+
+        let contents = output.contents();
+        let lines: Vec<&str> = contents.lines().collect();
+        assert_eq!(lines[0], "instances 0");
+        assert_eq!(lines[1], "freed 304");
+        assert!(lines[2..4].contains(&"deinit a") && lines[2..4].contains(&"deinit b"));
+        assert_eq!(lines[4], "left 1 0");
+    }
+
+    #[test]
+    fn test_collect_cycles_automatically() {
+        let mut interpreter = Interpreter::new();
+        interpreter.with_std().unwrap();
+        eval_with(
+            &mut interpreter,
+            "
+            var i = 0;
+            while (i < 25000) {
+                var node = { links = [] };
+                node.links.add(node);
+                i = i + 1;
+            }
+            var stats = memoryStats();
+            assert(stats.collections > 0, true);
+            assert(stats.objects < 15000, true);
+            ",
+        )
+        .unwrap();
+    }
+
+    #[test]
+    fn test_drop_interpreter_frees_global_cycles() {
+        let mut interpreter = Interpreter::new();
+        interpreter.with_std().unwrap();
+        eval_with(
+            &mut interpreter,
+            "var o = { links = [] }; o.links.add(o); var a = [o]; a.add(a);",
+        )
+        .unwrap();
+
+        let object = match interpreter.get_global("o").unwrap() {
+            Value::Object(object) => Rc::downgrade(&object),
+            _ => unreachable!(),
+        };
+        let array = match interpreter.get_global("a").unwrap() {
+            Value::Array(array) => Rc::downgrade(&array),
+            _ => unreachable!(),
+        };
+        drop(interpreter);
+
+        assert!(object.upgrade().is_none());
+        assert!(array.upgrade().is_none());
+    }
+
+    #[test]
+    fn test_instances_share_prototype() {
+        let mut interpreter = Interpreter::new();
+        interpreter.with_std().unwrap();
+        eval_with(
+            &mut interpreter,
+            "
+            object Circle {
+                init(radius) {}
+                area() { return 3 * this.radius * this.radius; }
+            }
+            var circles = [];
+            for (var r in range(0, 100)) { circles.add(Circle { radius = r }); }
+            assert(circles[2].area(), 12);
+            ",
+        )
+        .unwrap();
+
+        let prototype = interpreter.prototypes["Circle"].clone();
+        assert_eq!(Rc::strong_count(&prototype), 102);
+
+        let circles = interpreter.get_global("circles").unwrap();
+        let circle = circles.as_array().unwrap().borrow().elements[0].clone();
+        let circle = circle.as_object().unwrap();
+        assert!(circle.borrow().methods.is_empty());
+        assert!(Rc::ptr_eq(
+            circle.borrow().prototype.as_ref().unwrap(),
+            &prototype
+        ));
+    }
+
+    #[test]
+    fn test_string_building() {
+        let mut interpreter = Interpreter::new();
+        interpreter
+            .with_std()
+            .unwrap()
+            .with_limits(Limits::unlimited().with_max_allocated_bytes(1 << 20));
+        eval_with(
+            &mut interpreter,
+            "
+            var line = \"\";
+            var i = 0;
+            while (i < 20000) { line = line + \"*\"; i = i + 1; }
+
+            var a = \"ab\";
+            var b = a + \"c\";
+            var c = b + \"d\";
+            var d = b + \"e\";
+            assert(a, \"ab\");
+            assert(b, \"abc\");
+            assert(c, \"abcd\");
+            assert(d, \"abce\");
+            ",
+        )
+        .unwrap();
+
+        let line = interpreter.get_global("line").unwrap().as_string().unwrap();
+        assert_eq!(line.len(), 20000);
+        assert!(Rc::ptr_eq(&intern("line"), &intern(&String::from("line"))));
+    }
+
+    // Run with `cargo test --release -- --ignored --nocapture bench`.
+    #[test]
+    #[ignore]
+    fn bench_object_construction() {
+        fn construct(method_count: usize) -> std::time::Duration {
+            let methods: String = (0..method_count)
+                .map(|i| format!("m{}(a, b) {{ var x = a + b; if (x > 0) {{ return x * 2; }} return [a, b, {{ c = x }}]; }}\n", i))
+                .collect();
+            let source = format!(
+                "object Shape {{ init(x, y) {{}} {} }}
+                var i = 0;
+                while (i < 20000) {{ Shape {{ x = i, y = i }}; i = i + 1; }}",
+                methods
+            );
+            let mut interpreter = Interpreter::new();
+            interpreter.with_std().unwrap();
+            let start = std::time::Instant::now();
+            eval_with(&mut interpreter, &source).unwrap();
+            start.elapsed()
+        }
+
+        let few = construct(1);
+        let many = construct(100);
+        println!("1 method: {:?}, 100 methods: {:?}", few, many);
+        assert!(many < few * 2);
+    }
+
+    #[test]
+    fn test_read_input() {
+        let mut interpreter = Interpreter::new();
+        interpreter.with_std().unwrap();
+        interpreter.with_input(io::Cursor::new("Alice\r\n42\n"));
+        let output = interpreter.capture_output();
+
+        eval_with(
+            &mut interpreter,
+            r#"
+            var name = input("Name: ");
+            print("Hello,", name);
+            assert(readLine(), "42");
+            assert(readLine(), null);
+            "#,
+        )
+        .unwrap();
+
+        assert_eq!(output.contents(), "Name: Hello, Alice\n");
+    }
+
+    fn run_untrusted(source: &str) {
+        let mut interpreter = Interpreter::new();
+        interpreter.with_std().unwrap();
+        interpreter.capture_output();
+        interpreter.with_input(io::Cursor::new(""));
+
+        let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+            let tokens = Lexer::new(source).lex();
+            if let Ok(statements) = Parser::new(tokens).parse() {
+                let _ = interpreter.interprete(&statements);
+            }
+        }));
+        assert!(result.is_ok(), "script panicked: {}", source);
+    }
+
+    #[test]
+    fn test_hostile_scripts_do_not_panic() {
+        let scripts = [
+            "",
+            "(",
+            ")",
+            "{",
+            "}",
+            "[",
+            "]",
+            ".",
+            ",",
+            "var",
+            "var x",
+            "var x =",
+            "var x = ;",
+            "object",
+            "object {",
+            "object A { f( }",
+            "trait T { f() }",
+            "if (",
+            "if (true) {",
+            "while (true",
+            "for (var x in",
+            "for (var in x) {}",
+            "x.",
+            "x.1;",
+            "1 +",
+            "1 + ;",
+            "\"unterminated",
+            "&",
+            "|",
+            "@",
+            "é",
+            "var é = 1;",
+            "x = ;",
+            "= 1;",
+            "{ x = 1 ",
+            "Point { x = }",
+            "[1, 2",
+            "f(1, 2",
+            "a[1",
+            "return",
+            "break",
+            "continue",
+            "var s = \"héllo\"; print(s[10]); print(s[-1]); print(s[1.5]);",
+            "var a = [1]; print(a[5]); print(a[-1]); a[9] = 1;",
+            "1 / 0; 1 % 0;",
+            "undefined;",
+            "undefinedFn();",
+            "1();",
+            "null.x;",
+            "null[0];",
+            "var a = []; a.add(a); print(a); assert(a, a); print(a == [a]);",
+            "var o = { x = [] }; o.x.add(o); print(o); print(o == o);",
+            "var a = []; a.add(a); var b = []; b.add(b); print(a == b); print(a != [b]);",
+            "var o = { x = [] }; o.x.add(o); var p = { x = [] }; p.x.add(p); print(o == p);",
+            "object P { init(x) { x = x + 1; } } var p = P { x = 1 }; print(p.x);",
+            "var a = [1, 2, 3]; a.remove(2); for (var x in a) { a.add(x); } print(a);",
+            "range(0, 10, 0);",
+            "range(0, 10, -1);",
+            "print(range(0, 3).length());",
+            "assert(1, 2);",
+            "assert(1, 2, \"message\");",
+            "break;",
+            "continue;",
+            "return 1;",
+            "str();",
+            "\"a\" + 1;",
+            "-\"a\";",
+            "!1;",
+            "1 < \"a\";",
+            "true && 1;",
+            "object A { init() {} } var a = A { x = 1 };",
+            "A {};",
+            "this;",
+            "var x = 1; var x = 2;",
+            "object A { f() { break; } } A {}.f();",
+            "object A { f() { return this.g(); } } A {}.f();",
+            "[].insert(5, 1);",
+            "[].removeAt(0);",
+            "print(1, 2).x;",
+            "print.x;",
+            "var f = print; f();",
+            "readLine(); input(\"prompt\"); readLine(1, 2);",
+            "object A : Missing { }",
+            "trait T { f(); } object A : T { }",
+            "object A { f() { return this.f(); } } A {}.f();",
+            "throw;",
+            "throw null;",
+            "try {",
+            "try { } catch { }",
+            "catch (e) { }",
+            "try { throw 1; } catch (e) { throw e; }",
+            "try { break; } finally { }",
+        ];
+        for script in scripts {
+            run_untrusted(script);
+        }
+        eval("var a = []; a.add(a); var b = []; b.add(b); assert(a == b, true); assert(a == [1, a], false);");
+
+        run_untrusted(&format!("{}1{}", "(".repeat(10_000), ")".repeat(10_000)));
+        run_untrusted(&"if (true) { ".repeat(10_000));
+        run_untrusted(&"-".repeat(10_000));
+        run_untrusted(&"// comment\n".repeat(100_000));
+    }
+
+    #[test]
+    fn test_random_scripts_do_not_panic() {
+        let fragments = [
+            "var", "x", "y", "=", "1", "2.5", "\"s\"", "+", "-", "*", "/", "%", "==", "<", "&&",
+            "!", "(", ")", "{", "}", "[", "]", ".", ",", ";", ":", "if", "else", "for", "in",
+            "object", "trait", "return", "break", "continue", "true", "null", "print", "length",
+            "range", "assert", "A", "f", "init",
+        ];
+
+        // Fixed-seed linear congruential generator so failures are reproducible.
+        let mut seed: u64 = 0x5eed;
+        for _ in 0..2_000 {
+            let mut script = String::new();
+            for _ in 0..24 {
+                seed = seed
+                    .wrapping_mul(6364136223846793005)
+                    .wrapping_add(1442695040888963407);
+                script.push_str(fragments[(seed >> 33) as usize % fragments.len()]);
+                script.push(' ');
+            }
+            run_untrusted(&script);
+        }
+    }
+
+    fn limit_error(limits: Limits, source: &str) -> Error {
+        let mut interpreter = Interpreter::new();
+        interpreter.with_std().unwrap().with_limits(limits);
+        interpreter.capture_output();
+        eval_with(&mut interpreter, source).unwrap_err().into()
+    }
+
+    #[test]
+    fn test_execution_limits() {
+        let factorial = "
+            object Math {
+                factorial(n) {
+                    if (n <= 1) { return 1; }
+                    return n * factorial(n - 1);
+                }
+            }
+            print(Math {}.factorial(1000000));
+        ";
+        let error = limit_error(Limits::default(), factorial);
+        assert_eq!(error.kind, ErrorKind::RecursionLimit);
+        assert_eq!(error.message, "Maximum call depth of 200 exceeded");
+
+        let error = limit_error(
+            Limits::unlimited().with_max_steps(10_000),
+            "while (true) {}",
+        );
+        assert_eq!(error.kind, ErrorKind::StepLimit);
+
+        let error = limit_error(
+            Limits::unlimited().with_max_allocated_bytes(1 << 20),
+            "var s = \"x\"; while (true) { s = s + s; }",
+        );
+        assert_eq!(error.kind, ErrorKind::MemoryLimit);
+
+        let error = limit_error(
+            Limits::unlimited().with_max_allocated_bytes(1 << 20),
+            "var a = []; while (true) { a.add(1); }",
+        );
+        assert_eq!(error.kind, ErrorKind::MemoryLimit);
+
+        let error = limit_error(
+            Limits::unlimited().with_max_allocated_bytes(1 << 20),
+            "range(0, 1000000);",
+        );
+        assert_eq!(error.kind, ErrorKind::MemoryLimit);
+        assert_eq!(
+            error.message,
+            "Allocation limit of 1048576 bytes per run exceeded"
+        );
+    }
+
+    #[test]
+    fn test_allocation_limit_skips_shared_values() {
+        let mut interpreter = Interpreter::new();
+        interpreter
+            .with_std()
+            .unwrap()
+            .with_limits(Limits::unlimited().with_max_allocated_bytes(1 << 16));
+        interpreter
+            .register_function("lookup", 1, |interpreter, arguments| {
+                let name = arguments[0].as_string()?;
+                let name = name.as_str();
+                interpreter.env.get_value(&name)
+            })
+            .unwrap();
+
+        // Handing out an existing array allocates nothing, however often it happens.
+        eval_with(
+            &mut interpreter,
+            "var a = range(0, 100); var i = 0; while (i < 10000) { lookup(\"a\"); i = i + 1; }",
+        )
+        .unwrap();
+    }
+
+    #[test]
+    fn test_limits_apply_per_run() {
+        let mut interpreter = Interpreter::new();
+        interpreter
+            .with_std()
+            .unwrap()
+            .with_limits(Limits::unlimited().with_max_steps(1_000));
+        eval_with(
+            &mut interpreter,
+            "object Counter { tick() { var i = 0; while (i < 50) { i = i + 1; } } } var c = Counter {};",
+        )
+        .unwrap();
+
+        let counter = interpreter.get_global("c").unwrap();
+        for _ in 0..100 {
+            interpreter.call_method(&counter, "tick", &[]).unwrap();
+        }
+
+        assert!(eval_with(&mut interpreter, "c.tick(); c.tick(); c.tick(); c.tick();").is_err());
+        eval_with(&mut interpreter, "c.tick();").unwrap();
+    }
+
+    #[test]
+    fn test_interrupt() {
+        let mut interpreter = Interpreter::new();
+        interpreter.with_std().unwrap();
+        let handle = interpreter.interrupt_handle();
+
+        let stopper = std::thread::spawn(move || {
+            std::thread::sleep(std::time::Duration::from_millis(50));
+            handle.interrupt();
+        });
+        let error: Error = eval_with(&mut interpreter, "while (true) {}")
+            .unwrap_err()
+            .into();
+        stopper.join().unwrap();
+        assert_eq!(error.kind, ErrorKind::Interrupted);
+        assert_eq!(error.message, "Script interrupted");
+        eval_with(&mut interpreter, "var x = 1;").unwrap();
+
+        // An interrupt that arrives between runs stops the next one instead of being lost.
+        interpreter.interrupt_handle().interrupt();
+        let error: Error = eval_with(&mut interpreter, "x = 2;").unwrap_err().into();
+        assert_eq!(error.kind, ErrorKind::Interrupted);
+        eval_with(&mut interpreter, "assert(x, 1);").unwrap();
+    }
+
+    #[test]
+    fn test_timeout() {
+        let timeout = std::time::Duration::from_millis(50);
+        let error = limit_error(
+            Limits::default().with_timeout(timeout),
+            "object A { spin() { while (true) {} } } A {}.spin();",
+        );
+        assert_eq!(error.kind, ErrorKind::Interrupted);
+        assert_eq!(error.message, "Script timed out after 50ms");
+    }
+
+    #[test]
+    fn test_time() {
+        let clock = ManualClock::new().with_wall_clock(std::time::Duration::from_secs(1_000));
+        let mut interpreter = Interpreter::new();
+        interpreter.with_std().unwrap().with_clock(clock.clone());
+        eval_with(
+            &mut interpreter,
+            r#"
+            var start = time.now();
+            assert(time.wallClock(), 1000.0);
+            var watch = Stopwatch {};
+            var frame = 0;
+            while (frame < 60) {
+                time.sleep(1000 / 60.0);
+                frame = frame + 1;
+            }
+            assert(math.round(time.now() - start), 1.0);
+            assert(math.round(watch.elapsed() * 1000), 1000.0);
+
+            watch.stop();
+            time.sleep(500);
+            assert(watch.isRunning(), false);
+            assert(math.round(watch.elapsed()), 1.0);
+            watch.restart();
+            "#,
+        )
+        .unwrap();
+
+        clock.advance(std::time::Duration::from_millis(250));
+        eval_with(&mut interpreter, "assert(watch.elapsed(), 0.25);").unwrap();
+
+        // A manual clock jumps ahead in one step instead of sleeping in small slices.
+        let source = "time.sleep(1e15); assert(watch.elapsed(), 1e12 + 0.25);";
+        eval_with(&mut interpreter, source).unwrap();
+
+        let error = eval_with(&mut interpreter, "time.sleep(-1);").unwrap_err();
+        assert_eq!(
+            Error::from(error).message,
+            "Cannot sleep for -1 milliseconds"
+        );
+    }
+
+    #[test]
+    fn test_sleep_respects_timeout() {
+        let error = limit_error(
+            Limits::default().with_timeout(std::time::Duration::from_millis(50)),
+            "time.sleep(60000);",
+        );
+        assert_eq!(error.kind, ErrorKind::Interrupted);
+    }
+
+    #[test]
+    fn test_assert_reports_values() {
+        let mut interpreter = Interpreter::new();
+        interpreter.with_std().unwrap();
+
+        assert_eq!(
+            eval_with(&mut interpreter, "assert(1 + 1, 3);"),
+            Err(Flow::error("Assertion failed: 2 == 3".to_string()))
+        );
+        assert_eq!(
+            eval_with(&mut interpreter, r#"assert("a", "b", "letters differ");"#),
+            Err(Flow::error(
+                "Assertion failed: letters differ (left: a, right: b)".to_string()
+            ))
+        );
+        assert_eq!(
+            eval_with(&mut interpreter, r#"var s = "abc"; s[3];"#),
+            Err(Flow::error("Index out of bounds".to_string()))
+        );
+    }
 }
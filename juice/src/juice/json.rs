@@ -0,0 +1,449 @@
+use super::{
+    flow::Flow, interpreter::Interpreter, limits::Budget, native_function::NativeFunction,
+    object::Object, symbol::Symbol, value::Value,
+};
+use std::{fmt::Write, rc::Rc};
+
+const MAX_DEPTH: usize = 128;
+
+/// Builds the `json` namespace object that `with_std` exposes to scripts.
+pub fn module() -> Result<Value, Flow> {
+    let mut json = Object::new();
+    json.define_method(
+        "parse",
+        Value::new_native_function(NativeFunction::new(parse, 1, 1)),
+    )?;
+    json.define_method(
+        "stringify",
+        Value::new_native_function(NativeFunction::new(stringify, 1, 2)),
+    )?;
+    Ok(Value::new_object(json))
+}
+
+fn parse(interpreter: &mut Interpreter, values: &[Value]) -> Result<Value, Flow> {
+    let source = values[0].as_string()?;
+    let source = source.as_str();
+    interpreter.budget.allocate(source.len())?;
+    let mut parser = Parser::new(&source);
+    let value = parser.value()?;
+    parser.skip_whitespace();
+    if parser.peek().is_some() {
+        return Err(parser.error("Unexpected trailing characters"));
+    }
+    Ok(value)
+}
+
+fn stringify(interpreter: &mut Interpreter, values: &[Value]) -> Result<Value, Flow> {
+    let indent = match values.get(1) {
+        None | Some(Value::Null) => None,
+        Some(Value::Integer(n)) if (0..=10).contains(n) => Some(" ".repeat(*n as usize)),
+        Some(Value::String(s)) => Some(s.to_string()),
+        Some(value) => {
+            return Err(Flow::error(format!(
+                "Invalid indent {}, expected 0 to 10 spaces or a string",
+                value
+            )))
+        }
+    };
+
+    let mut writer = Writer {
+        output: String::new(),
+        indent,
+        visiting: Vec::new(),
+        budget: &mut interpreter.budget,
+        charged: 0,
+    };
+    writer.value(&values[0], 0)?;
+    writer.charge()?;
+    Ok(Value::new_string(writer.output))
+}
+
+struct Parser {
+    chars: Vec<char>,
+    position: usize,
+    line: usize,
+    column: usize,
+    depth: usize,
+}
+
+impl Parser {
+    fn new(source: &str) -> Self {
+        Self {
+            chars: source.chars().collect(),
+            position: 0,
+            line: 1,
+            column: 1,
+            depth: 0,
+        }
+    }
+
+    fn error(&self, message: &str) -> Flow {
+        Flow::error(format!(
+            "Invalid JSON: {} at line {}, column {}",
+            message, self.line, self.column
+        ))
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.position).copied()
+    }
+
+    fn advance(&mut self) -> Option<char> {
+        let c = self.peek()?;
+        self.position += 1;
+        if c == '\n' {
+            self.line += 1;
+            self.column = 1;
+        } else {
+            self.column += 1;
+        }
+        Some(c)
+    }
+
+    fn expect(&mut self, expected: char) -> Result<(), Flow> {
+        match self.peek() {
+            Some(c) if c == expected => {
+                self.advance();
+                Ok(())
+            }
+            Some(c) => Err(self.error(&format!("Expected '{}' but found '{}'", expected, c))),
+            None => Err(self.error(&format!("Expected '{}' but found end of input", expected))),
+        }
+    }
+
+    fn skip_whitespace(&mut self) {
+        while matches!(self.peek(), Some(' ' | '\t' | '\n' | '\r')) {
+            self.advance();
+        }
+    }
+
+    fn value(&mut self) -> Result<Value, Flow> {
+        self.skip_whitespace();
+        match self.peek() {
+            Some('{') => self.nested(Self::object),
+            Some('[') => self.nested(Self::array),
+            Some('"') => Ok(Value::new_string(self.string()?)),
+            Some('-' | '0'..='9') => self.number(),
+            Some('t') => self.keyword("true", Value::Bool(true)),
+            Some('f') => self.keyword("false", Value::Bool(false)),
+            Some('n') => self.keyword("null", Value::Null),
+            Some(c) => Err(self.error(&format!("Unexpected character '{}'", c))),
+            None => Err(self.error("Unexpected end of input")),
+        }
+    }
+
+    fn nested(&mut self, parse: fn(&mut Self) -> Result<Value, Flow>) -> Result<Value, Flow> {
+        if self.depth >= MAX_DEPTH {
+            return Err(self.error("Nested too deeply"));
+        }
+        self.depth += 1;
+        let value = parse(self);
+        self.depth -= 1;
+        value
+    }
+
+    fn keyword(&mut self, keyword: &str, value: Value) -> Result<Value, Flow> {
+        for expected in keyword.chars() {
+            if self.peek() != Some(expected) {
+                return Err(self.error(&format!("Expected '{}'", keyword)));
+            }
+            self.advance();
+        }
+        Ok(value)
+    }
+
+    fn object(&mut self) -> Result<Value, Flow> {
+        self.expect('{')?;
+        let mut object = Object::new();
+        self.skip_whitespace();
+        if self.peek() == Some('}') {
+            self.advance();
+            return Ok(Value::new_object(object));
+        }
+
+        loop {
+            self.skip_whitespace();
+            if self.peek() != Some('"') {
+                return Err(self.error("Expected string key"));
+            }
+            let key = self.string()?;
+            self.skip_whitespace();
+            self.expect(':')?;
+            let value = self.value()?;
+            // Like most parsers, a repeated key keeps the last value. Keys are data, not names
+            // from the source, so they are not interned and are freed with the object.
+            object.values.insert(Symbol::from(key), value);
+
+            self.skip_whitespace();
+            match self.peek() {
+                Some(',') => self.advance(),
+                Some('}') => {
+                    self.advance();
+                    return Ok(Value::new_object(object));
+                }
+                _ => return Err(self.error("Expected ',' or '}' in object")),
+            };
+        }
+    }
+
+    fn array(&mut self) -> Result<Value, Flow> {
+        self.expect('[')?;
+        let mut elements = Vec::new();
+        self.skip_whitespace();
+        if self.peek() == Some(']') {
+            self.advance();
+            return Value::new_array(elements);
+        }
+
+        loop {
+            elements.push(self.value()?);
+            self.skip_whitespace();
+            match self.peek() {
+                Some(',') => self.advance(),
+                Some(']') => {
+                    self.advance();
+                    return Value::new_array(elements);
+                }
+                _ => return Err(self.error("Expected ',' or ']' in array")),
+            };
+        }
+    }
+
+    fn string(&mut self) -> Result<String, Flow> {
+        self.expect('"')?;
+        let mut string = String::new();
+        loop {
+            match self.advance() {
+                Some('"') => return Ok(string),
+                Some('\\') => string.push(self.escape()?),
+                Some(c) if (c as u32) < 0x20 => {
+                    return Err(self.error("Control character in string"))
+                }
+                Some(c) => string.push(c),
+                None => return Err(self.error("Unterminated string")),
+            }
+        }
+    }
+
+    fn escape(&mut self) -> Result<char, Flow> {
+        match self.advance() {
+            Some('"') => Ok('"'),
+            Some('\\') => Ok('\\'),
+            Some('/') => Ok('/'),
+            Some('b') => Ok('\u{8}'),
+            Some('f') => Ok('\u{c}'),
+            Some('n') => Ok('\n'),
+            Some('r') => Ok('\r'),
+            Some('t') => Ok('\t'),
+            Some('u') => {
+                let high = self.hex_escape()?;
+                if !(0xd800..0xdc00).contains(&high) {
+                    return char::from_u32(high)
+                        .ok_or_else(|| self.error("Invalid unicode escape"));
+                }
+                // A high surrogate must be followed by an escaped low surrogate.
+                if self.advance() != Some('\\') || self.advance() != Some('u') {
+                    return Err(self.error("Unpaired surrogate in unicode escape"));
+                }
+                let low = self.hex_escape()?;
+                if !(0xdc00..0xe000).contains(&low) {
+                    return Err(self.error("Unpaired surrogate in unicode escape"));
+                }
+                let code = 0x10000 + ((high - 0xd800) << 10) + (low - 0xdc00);
+                char::from_u32(code).ok_or_else(|| self.error("Invalid unicode escape"))
+            }
+            _ => Err(self.error("Invalid escape sequence")),
+        }
+    }
+
+    fn hex_escape(&mut self) -> Result<u32, Flow> {
+        let mut code = 0;
+        for _ in 0..4 {
+            match self.advance().and_then(|c| c.to_digit(16)) {
+                Some(digit) => code = code * 16 + digit,
+                None => return Err(self.error("Invalid unicode escape")),
+            }
+        }
+        Ok(code)
+    }
+
+    fn number(&mut self) -> Result<Value, Flow> {
+        let start = self.position;
+        let (line, column) = (self.line, self.column);
+        let mut is_float = false;
+
+        if self.peek() == Some('-') {
+            self.advance();
+        }
+        match self.peek() {
+            Some('0') => {
+                self.advance();
+            }
+            Some('1'..='9') => self.digits(),
+            _ => return Err(self.error("Expected digit")),
+        }
+        if self.peek() == Some('.') {
+            is_float = true;
+            self.advance();
+            if !self.peek().is_some_and(|c| c.is_ascii_digit()) {
+                return Err(self.error("Expected digit after '.'"));
+            }
+            self.digits();
+        }
+        if matches!(self.peek(), Some('e' | 'E')) {
+            is_float = true;
+            self.advance();
+            if matches!(self.peek(), Some('+' | '-')) {
+                self.advance();
+            }
+            if !self.peek().is_some_and(|c| c.is_ascii_digit()) {
+                return Err(self.error("Expected digit in exponent"));
+            }
+            self.digits();
+        }
+
+        let text: String = self.chars[start..self.position].iter().collect();
+        // Integers too large for i64 fall back to floats, as in JavaScript.
+        if !is_float {
+            if let Ok(n) = text.parse::<i64>() {
+                return Ok(Value::Integer(n));
+            }
+        }
+        match text.parse::<f64>() {
+            Ok(n) if n.is_finite() => Ok(Value::Number(n)),
+            _ => Err(Flow::error(format!(
+                "Invalid JSON: Number {} is out of range at line {}, column {}",
+                text, line, column
+            ))),
+        }
+    }
+
+    fn digits(&mut self) {
+        while self.peek().is_some_and(|c| c.is_ascii_digit()) {
+            self.advance();
+        }
+    }
+}
+
+struct Writer<'a> {
+    output: String,
+    indent: Option<String>,
+    visiting: Vec<*const ()>,
+    budget: &'a mut Budget,
+    charged: usize,
+}
+
+impl Writer<'_> {
+    fn value(&mut self, value: &Value, depth: usize) -> Result<(), Flow> {
+        // Charging as the output grows stops a shared value repeated many times from
+        // building a huge string before the memory limit is checked.
+        self.charge()?;
+        match value {
+            Value::Null => self.output.push_str("null"),
+            Value::Bool(b) => write!(self.output, "{}", b).unwrap(),
+            Value::Integer(n) => write!(self.output, "{}", n).unwrap(),
+            Value::Number(n) if n.is_finite() => write!(self.output, "{:?}", n).unwrap(),
+            Value::Number(n) => return Err(Flow::error(format!("Cannot stringify {}", n))),
+            Value::String(s) => self.string(&s.as_str()),
+            Value::Array(array) => {
+                self.enter(Rc::as_ptr(array) as *const ())?;
+                let elements = array.borrow().elements.clone();
+                self.output.push('[');
+                for (i, element) in elements.iter().enumerate() {
+                    if i > 0 {
+                        self.output.push(',');
+                    }
+                    self.newline(depth + 1);
+                    self.value(element, depth + 1)?;
+                }
+                if !elements.is_empty() {
+                    self.newline(depth);
+                }
+                self.output.push(']');
+                self.visiting.pop();
+            }
+            Value::Object(object) => {
+                self.enter(Rc::as_ptr(object) as *const ())?;
+                // Keys are sorted so the same data always produces the same text.
+                let mut fields: Vec<_> = object
+                    .borrow()
+                    .values
+                    .iter()
+                    .map(|(name, value)| (name.clone(), value.clone()))
+                    .collect();
+                fields.sort_by(|a, b| a.0.cmp(&b.0));
+                self.output.push('{');
+                for (i, (name, value)) in fields.iter().enumerate() {
+                    if i > 0 {
+                        self.output.push(',');
+                    }
+                    self.newline(depth + 1);
+                    self.string(name);
+                    self.output.push(':');
+                    if self.indent.is_some() {
+                        self.output.push(' ');
+                    }
+                    self.value(value, depth + 1)?;
+                }
+                if !fields.is_empty() {
+                    self.newline(depth);
+                }
+                self.output.push('}');
+                self.visiting.pop();
+            }
+            _ => {
+                return Err(Flow::error(format!(
+                    "Cannot stringify a value of type {}",
+                    value.type_name()
+                )))
+            }
+        }
+        Ok(())
+    }
+
+    fn enter(&mut self, container: *const ()) -> Result<(), Flow> {
+        if self.visiting.contains(&container) {
+            return Err(Flow::error(
+                "Cannot stringify a cyclic structure".to_string(),
+            ));
+        }
+        if self.visiting.len() >= MAX_DEPTH {
+            return Err(Flow::error(
+                "Cannot stringify a value nested this deeply".to_string(),
+            ));
+        }
+        self.visiting.push(container);
+        Ok(())
+    }
+
+    fn charge(&mut self) -> Result<(), Flow> {
+        self.budget.allocate(self.output.len() - self.charged)?;
+        self.charged = self.output.len();
+        Ok(())
+    }
+
+    fn newline(&mut self, depth: usize) {
+        if let Some(indent) = &self.indent {
+            self.output.push('\n');
+            for _ in 0..depth {
+                self.output.push_str(indent);
+            }
+        }
+    }
+
+    fn string(&mut self, string: &str) {
+        self.output.push('"');
+        for c in string.chars() {
+            match c {
+                '"' => self.output.push_str("\\\""),
+                '\\' => self.output.push_str("\\\\"),
+                '\n' => self.output.push_str("\\n"),
+                '\r' => self.output.push_str("\\r"),
+                '\t' => self.output.push_str("\\t"),
+                c if (c as u32) < 0x20 => write!(self.output, "\\u{:04x}", c as u32).unwrap(),
+                c => self.output.push(c),
+            }
+        }
+        self.output.push('"');
+    }
+}
@@ -0,0 +1,197 @@
+use super::{error::ErrorKind, flow::Flow, interrupt::InterruptHandle, string::Str, value::Value};
+use std::{
+    mem::size_of,
+    rc::Rc,
+    time::{Duration, Instant},
+};
+
+pub const DEFAULT_MAX_CALL_DEPTH: usize = 200;
+
+/// Bounds on what a single run of a script may consume; `None` means unlimited.
+///
+/// `max_allocated_bytes` caps the total bytes allocated over a run, not live memory: freeing a
+/// value does not give its bytes back.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Limits {
+    pub max_call_depth: Option<usize>,
+    pub max_steps: Option<u64>,
+    pub max_allocated_bytes: Option<usize>,
+    pub timeout: Option<Duration>,
+}
+
+impl Limits {
+    pub fn unlimited() -> Self {
+        Self {
+            max_call_depth: None,
+            max_steps: None,
+            max_allocated_bytes: None,
+            timeout: None,
+        }
+    }
+
+    pub fn with_max_call_depth(mut self, max_call_depth: usize) -> Self {
+        self.max_call_depth = Some(max_call_depth);
+        self
+    }
+
+    pub fn with_max_steps(mut self, max_steps: u64) -> Self {
+        self.max_steps = Some(max_steps);
+        self
+    }
+
+    pub fn with_max_allocated_bytes(mut self, max_allocated_bytes: usize) -> Self {
+        self.max_allocated_bytes = Some(max_allocated_bytes);
+        self
+    }
+
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+}
+
+impl Default for Limits {
+    fn default() -> Self {
+        Self {
+            max_call_depth: Some(DEFAULT_MAX_CALL_DEPTH),
+            max_steps: None,
+            max_allocated_bytes: None,
+            timeout: None,
+        }
+    }
+}
+
+/// Tracks usage against `Limits`; counters restart whenever a new outermost run begins.
+#[derive(Debug, Clone, Default)]
+pub struct Budget {
+    pub limits: Limits,
+    pub runs: usize,
+    pub call_depth: usize,
+    pub steps: u64,
+    pub allocated: usize,
+    pub interrupt: InterruptHandle,
+    pub deadline: Option<Instant>,
+}
+
+impl Budget {
+    pub fn new(limits: Limits) -> Self {
+        Self {
+            limits,
+            runs: 0,
+            call_depth: 0,
+            steps: 0,
+            allocated: 0,
+            interrupt: InterruptHandle::new(),
+            deadline: None,
+        }
+    }
+
+    pub fn reset(&mut self) {
+        self.call_depth = 0;
+        self.steps = 0;
+        self.allocated = 0;
+        self.deadline = self.limits.timeout.map(|timeout| Instant::now() + timeout);
+    }
+
+    pub fn enter_call(&mut self) -> Result<(), Flow> {
+        if let Some(max) = self.limits.max_call_depth {
+            if self.call_depth >= max {
+                return Err(Flow::error_of_kind(
+                    ErrorKind::RecursionLimit,
+                    format!("Maximum call depth of {} exceeded", max),
+                ));
+            }
+        }
+        self.call_depth += 1;
+        Ok(())
+    }
+
+    pub fn leave_call(&mut self) {
+        self.call_depth = self.call_depth.saturating_sub(1);
+    }
+
+    pub fn step(&mut self) -> Result<(), Flow> {
+        self.steps += 1;
+        if self.interrupt.is_interrupted() {
+            return Err(interrupted());
+        }
+        // Reading the clock on every step is measurably slow, so the deadline is polled.
+        if self.steps.is_multiple_of(1024) {
+            self.check_deadline()?;
+        }
+        match self.limits.max_steps {
+            Some(max) if self.steps > max => Err(Flow::error_of_kind(
+                ErrorKind::StepLimit,
+                format!("Step limit of {} exceeded", max),
+            )),
+            _ => Ok(()),
+        }
+    }
+
+    /// Fails if the run was interrupted or timed out, for host code that blocks between steps.
+    pub fn check_interrupt(&self) -> Result<(), Flow> {
+        if self.interrupt.is_interrupted() {
+            return Err(interrupted());
+        }
+        self.check_deadline()
+    }
+
+    fn check_deadline(&self) -> Result<(), Flow> {
+        if let (Some(deadline), Some(timeout)) = (self.deadline, self.limits.timeout) {
+            if Instant::now() >= deadline {
+                return Err(Flow::error_of_kind(
+                    ErrorKind::Interrupted,
+                    format!("Script timed out after {:?}", timeout),
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    pub fn allocate(&mut self, bytes: usize) -> Result<(), Flow> {
+        self.allocated = self.allocated.saturating_add(bytes);
+        match self.limits.max_allocated_bytes {
+            Some(max) if self.allocated > max => Err(Flow::error_of_kind(
+                ErrorKind::MemoryLimit,
+                format!("Allocation limit of {} bytes per run exceeded", max),
+            )),
+            _ => Ok(()),
+        }
+    }
+
+    pub fn allocate_value(&mut self, value: &Value) -> Result<(), Flow> {
+        self.allocate(allocation_size(value))
+    }
+
+    /// Charges a value returned from native code only if nothing else refers to it, since a
+    /// shared one was charged when it was first made.
+    pub fn allocate_returned(&mut self, value: &Value) -> Result<(), Flow> {
+        if is_unique(value) {
+            self.allocate_value(value)?;
+        }
+        Ok(())
+    }
+}
+
+fn interrupted() -> Flow {
+    Flow::error_of_kind(ErrorKind::Interrupted, "Script interrupted")
+}
+
+pub fn allocation_size(value: &Value) -> usize {
+    match value {
+        Value::String(string) => string.len(),
+        Value::Array(array) => array.borrow().elements.len() * size_of::<Value>(),
+        Value::Object(object) => object.borrow().values.len() * size_of::<Value>(),
+        _ => 0,
+    }
+}
+
+fn is_unique(value: &Value) -> bool {
+    match value {
+        Value::String(Str::Shared(string)) => Rc::strong_count(string) == 1,
+        Value::String(Str::Buffer { buffer, .. }) => Rc::strong_count(buffer) == 1,
+        Value::Array(array) => Rc::strong_count(array) == 1,
+        Value::Object(object) => Rc::strong_count(object) == 1,
+        _ => false,
+    }
+}
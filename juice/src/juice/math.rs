@@ -0,0 +1,190 @@
+use super::{
+    convert::{FromValue, IntoNativeFunction},
+    flow::Flow,
+    interpreter::Interpreter,
+    native_function::NativeFunction,
+    object::Object,
+    value::Value,
+};
+use std::{cmp::Ordering, f64::consts};
+
+type UnaryFn = fn(f64) -> f64;
+
+/// Builds the `math` namespace object that `with_std` exposes to scripts.
+pub fn module() -> Result<Value, Flow> {
+    let mut math = Object::new();
+
+    math.define_value("PI", Value::Number(consts::PI))?;
+    math.define_value("TAU", Value::Number(consts::TAU))?;
+    math.define_value("E", Value::Number(consts::E))?;
+    math.define_value("INFINITY", Value::Number(f64::INFINITY))?;
+    math.define_value("MAX_INT", Value::Integer(i64::MAX))?;
+    math.define_value("MIN_INT", Value::Integer(i64::MIN))?;
+
+    let unary: [(&str, UnaryFn); 20] = [
+        ("sqrt", f64::sqrt),
+        ("cbrt", f64::cbrt),
+        ("exp", f64::exp),
+        ("ln", f64::ln),
+        ("log2", f64::log2),
+        ("log10", f64::log10),
+        ("sin", f64::sin),
+        ("cos", f64::cos),
+        ("tan", f64::tan),
+        ("asin", f64::asin),
+        ("acos", f64::acos),
+        ("atan", f64::atan),
+        ("sinh", f64::sinh),
+        ("cosh", f64::cosh),
+        ("tanh", f64::tanh),
+        ("floor", f64::floor),
+        ("ceil", f64::ceil),
+        ("round", f64::round),
+        ("trunc", f64::trunc),
+        ("fract", f64::fract),
+    ];
+    for (name, function) in unary {
+        define(&mut math, name, function)?;
+    }
+
+    define(&mut math, "atan2", f64::atan2)?;
+    define(&mut math, "pow", f64::powf)?;
+    define(&mut math, "hypot", f64::hypot)?;
+    define(&mut math, "log", |x: f64, base: f64| x.log(base))?;
+    define(&mut math, "lerp", |a: f64, b: f64, t: f64| a + (b - a) * t)?;
+    define(&mut math, "isNaN", f64::is_nan)?;
+    define(&mut math, "toFloat", |x: f64| x)?;
+    define(&mut math, "toInt", to_int)?;
+    define(&mut math, "gcd", gcd)?;
+
+    define_native(&mut math, "abs", abs, 1, 1)?;
+    define_native(&mut math, "sign", sign, 1, 1)?;
+    define_native(&mut math, "min", min, 1, 256)?;
+    define_native(&mut math, "max", max, 1, 256)?;
+    define_native(&mut math, "clamp", clamp, 3, 3)?;
+
+    Ok(Value::new_object(math))
+}
+
+fn define<Args>(
+    math: &mut Object,
+    name: &str,
+    function: impl IntoNativeFunction<Args>,
+) -> Result<Value, Flow> {
+    math.define_method(
+        name,
+        Value::new_native_function(function.into_native_function()),
+    )
+}
+
+fn define_native(
+    math: &mut Object,
+    name: &str,
+    function: fn(&mut Interpreter, &[Value]) -> Result<Value, Flow>,
+    min_arity: usize,
+    max_arity: usize,
+) -> Result<Value, Flow> {
+    math.define_method(
+        name,
+        Value::new_native_function(NativeFunction::new(function, min_arity, max_arity)),
+    )
+}
+
+fn to_int(x: f64) -> Result<i64, Flow> {
+    let x = x.trunc();
+    if x.is_finite() && x >= i64::MIN as f64 && x < i64::MAX as f64 {
+        Ok(x as i64)
+    } else {
+        Err(Flow::error(format!("Cannot convert {} to an integer", x)))
+    }
+}
+
+fn gcd(a: i64, b: i64) -> Result<i64, Flow> {
+    let (mut a, mut b) = (a.unsigned_abs(), b.unsigned_abs());
+    while b != 0 {
+        (a, b) = (b, a % b);
+    }
+    i64::try_from(a).map_err(|_| Flow::error("Integer overflow".to_string()))
+}
+
+// The helpers below keep integers as integers and only fall back to floats when a
+// float is involved, like the arithmetic operators do.
+
+fn abs(_: &mut Interpreter, values: &[Value]) -> Result<Value, Flow> {
+    match &values[0] {
+        Value::Integer(n) => match n.checked_abs() {
+            Some(n) => Ok(Value::Integer(n)),
+            None => Err(Flow::error("Integer overflow".to_string())),
+        },
+        value => Ok(Value::Number(f64::from_value(value)?.abs())),
+    }
+}
+
+fn sign(_: &mut Interpreter, values: &[Value]) -> Result<Value, Flow> {
+    match &values[0] {
+        Value::Integer(n) => Ok(Value::Integer(n.signum())),
+        value => {
+            let x = f64::from_value(value)?;
+            Ok(Value::Number(if x == 0.0 { 0.0 } else { x.signum() }))
+        }
+    }
+}
+
+fn min(_: &mut Interpreter, values: &[Value]) -> Result<Value, Flow> {
+    pick(values, Ordering::Less)
+}
+
+fn max(_: &mut Interpreter, values: &[Value]) -> Result<Value, Flow> {
+    pick(values, Ordering::Greater)
+}
+
+fn pick(values: &[Value], wanted: Ordering) -> Result<Value, Flow> {
+    let mut best = &values[0];
+    for value in values {
+        let ordering = match (value, best) {
+            // Compared exactly, since large integers lose precision as floats.
+            (Value::Integer(a), Value::Integer(b)) => Some(a.cmp(b)),
+            _ => f64::from_value(value)?.partial_cmp(&f64::from_value(best)?),
+        };
+        if ordering == Some(wanted) {
+            best = value;
+        }
+    }
+
+    if values
+        .iter()
+        .all(|value| matches!(value, Value::Integer(_)))
+    {
+        Ok(best.clone())
+    } else {
+        Ok(Value::Number(f64::from_value(best)?))
+    }
+}
+
+fn clamp(_: &mut Interpreter, values: &[Value]) -> Result<Value, Flow> {
+    match (&values[0], &values[1], &values[2]) {
+        (Value::Integer(x), Value::Integer(low), Value::Integer(high)) => {
+            if low > high {
+                return Err(Flow::error(format!(
+                    "Invalid clamp range {} to {}",
+                    low, high
+                )));
+            }
+            Ok(Value::Integer(*x.clamp(low, high)))
+        }
+        (x, low, high) => {
+            let (x, low, high) = (
+                f64::from_value(x)?,
+                f64::from_value(low)?,
+                f64::from_value(high)?,
+            );
+            if low.is_nan() || high.is_nan() || low > high {
+                return Err(Flow::error(format!(
+                    "Invalid clamp range {:?} to {:?}",
+                    low, high
+                )));
+            }
+            Ok(Value::Number(x.clamp(low, high)))
+        }
+    }
+}
@@ -2,16 +2,16 @@ use super::{flow::Flow, interpreter::Interpreter, object::Object, traits::Callab
 use crate::ast::MethodDeclaration;
 use std::{cell::RefCell, rc::Rc};
 
-#[derive(Clone, PartialEq)]
+#[derive(Clone)]
 pub struct Method {
-    pub declaration: MethodDeclaration,
+    pub declaration: Rc<MethodDeclaration>,
     pub this: Option<Rc<RefCell<Object>>>,
     pub min_arity: usize,
     pub max_arity: usize,
 }
 
 impl Method {
-    pub fn new(declaration: MethodDeclaration) -> Self {
+    pub fn new(declaration: Rc<MethodDeclaration>) -> Self {
         let arity = declaration.signature.params.len();
         Self {
             declaration,
@@ -26,12 +26,23 @@ impl Method {
     }
 }
 
+impl PartialEq for Method {
+    fn eq(&self, other: &Self) -> bool {
+        let same_this = match (&self.this, &other.this) {
+            (Some(a), Some(b)) => Rc::ptr_eq(a, b),
+            (None, None) => true,
+            _ => false,
+        };
+        same_this && self.declaration.signature == other.declaration.signature
+    }
+}
+
 impl Callable for Method {
-    fn call(&self, interpreter: &mut Interpreter, arguments: &Vec<Value>) -> Result<Value, Flow> {
+    fn call(&self, interpreter: &mut Interpreter, arguments: &[Value]) -> Result<Value, Flow> {
         let arity = arguments.len();
         if arity < self.min_arity || arity > self.max_arity {
             if self.min_arity != self.max_arity {
-                return Err(Flow::Error(format!(
+                return Err(Flow::error(format!(
                     "Expected ({}, {}] arguments but got {}",
                     self.min_arity,
                     self.max_arity,
@@ -39,7 +50,7 @@ impl Callable for Method {
                 )));
             }
 
-            return Err(Flow::Error(format!(
+            return Err(Flow::error(format!(
                 "Expected {} arguments but got {}",
                 arity,
                 arguments.len()
@@ -47,6 +58,7 @@ impl Callable for Method {
         }
 
         if let Some(this) = self.this.clone() {
+            interpreter.budget.enter_call()?;
             interpreter.env.push(this.clone());
             interpreter.env.push_default();
 
@@ -63,6 +75,7 @@ impl Callable for Method {
             }
             let ret = interpreter.execute_statements(&self.declaration.body);
 
+            interpreter.budget.leave_call();
             interpreter.env.pop()?;
             interpreter.env.pop()?;
 
@@ -70,15 +83,20 @@ impl Callable for Method {
                 Ok(_) => Ok(Value::Void),
                 Err(flow) => match flow {
                     Flow::Return(value) => Ok(value),
-                    Flow::Break => Err(Flow::Error("Break statement outside of loop".to_string())),
-                    Flow::Continue => Err(Flow::Error(
+                    Flow::Break => Err(Flow::error("Break statement outside of loop".to_string())),
+                    Flow::Continue => Err(Flow::error(
                         "Continue statement outside of loop".to_string(),
                     )),
-                    Flow::Error(err) => Err(Flow::Error(err)),
+                    Flow::Error(mut error) => {
+                        error
+                            .stack
+                            .push(self.declaration.signature.name.to_string());
+                        Err(Flow::Error(error))
+                    }
                 },
             }
         } else {
-            return Err(Flow::Error("Method not bound to an object".to_string()));
+            return Err(Flow::error("Method not bound to an object".to_string()));
         }
     }
 }
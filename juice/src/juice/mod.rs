@@ -1,11 +1,26 @@
 pub mod array;
 pub mod builtin_function;
+pub mod convert;
 pub mod env;
+pub mod error;
 pub mod flow;
+pub mod fs;
+pub mod gc;
+pub mod interrupt;
+pub mod json;
+pub mod limits;
+pub mod math;
 pub mod method;
 pub mod native_function;
 pub mod native_method;
+pub mod native_object;
 pub mod object;
+pub mod process;
+pub mod random;
+pub mod stream;
+pub mod string;
+pub mod symbol;
+pub mod time;
 pub mod traits;
 pub mod value;
 pub mod interpreter;
@@ -1,32 +1,41 @@
 use super::{flow::Flow, interpreter::Interpreter, traits::Callable, value::Value};
+use std::rc::Rc;
 
-#[derive(Clone, PartialEq)]
+pub type NativeFn = dyn Fn(&mut Interpreter, &[Value]) -> Result<Value, Flow>;
+
+#[derive(Clone)]
 pub struct NativeFunction {
-    pub function: fn(&Vec<Value>) -> Result<Value, Flow>,
+    pub function: Rc<NativeFn>,
     pub min_arity: usize,
     pub max_arity: usize,
 }
 
 impl NativeFunction {
     pub fn new(
-        function: fn(&Vec<Value>) -> Result<Value, Flow>,
+        function: impl Fn(&mut Interpreter, &[Value]) -> Result<Value, Flow> + 'static,
         min_arity: usize,
         max_arity: usize,
     ) -> Self {
         Self {
-            function,
+            function: Rc::new(function),
             min_arity,
             max_arity,
         }
     }
 }
 
+impl PartialEq for NativeFunction {
+    fn eq(&self, other: &Self) -> bool {
+        Rc::ptr_eq(&self.function, &other.function)
+    }
+}
+
 impl Callable for NativeFunction {
-    fn call(&self, _: &mut Interpreter, arguments: &Vec<Value>) -> Result<Value, Flow> {
+    fn call(&self, interpreter: &mut Interpreter, arguments: &[Value]) -> Result<Value, Flow> {
         let arity = arguments.len();
         if arity < self.min_arity || arity > self.max_arity {
             if self.min_arity != self.max_arity {
-                return Err(Flow::Error(format!(
+                return Err(Flow::error(format!(
                     "Expected ({}, {}] arguments but got {}",
                     self.min_arity,
                     self.max_arity,
@@ -34,13 +43,13 @@ impl Callable for NativeFunction {
                 )));
             }
 
-            return Err(Flow::Error(format!(
+            return Err(Flow::error(format!(
                 "Expected {} arguments but got {}",
-                arity,
+                self.min_arity,
                 arguments.len()
             )));
         }
 
-        (self.function)(arguments)
+        (self.function)(interpreter, arguments)
     }
 }
@@ -1,9 +1,11 @@
 use super::{flow::Flow, interpreter::Interpreter, traits::Callable, value::Value};
 use std::{cell::RefCell, rc::Rc};
 
-#[derive(Clone, PartialEq)]
+pub type NativeMethodFn = dyn Fn(&mut Interpreter, &Value, &[Value]) -> Result<Value, Flow>;
+
+#[derive(Clone)]
 pub struct NativeMethod {
-    pub function: fn(this: &Value, &Vec<Value>) -> Result<Value, Flow>,
+    pub function: Rc<NativeMethodFn>,
     pub this: Rc<RefCell<Value>>,
     pub min_arity: usize,
     pub max_arity: usize,
@@ -11,13 +13,13 @@ pub struct NativeMethod {
 
 impl NativeMethod {
     pub fn new(
-        function: fn(&Value, &Vec<Value>) -> Result<Value, Flow>,
+        function: impl Fn(&mut Interpreter, &Value, &[Value]) -> Result<Value, Flow> + 'static,
         this: Rc<RefCell<Value>>,
         min_arity: usize,
         max_arity: usize,
     ) -> Self {
         Self {
-            function,
+            function: Rc::new(function),
             this,
             min_arity,
             max_arity,
@@ -25,12 +27,18 @@ impl NativeMethod {
     }
 }
 
+impl PartialEq for NativeMethod {
+    fn eq(&self, other: &Self) -> bool {
+        Rc::ptr_eq(&self.function, &other.function) && Rc::ptr_eq(&self.this, &other.this)
+    }
+}
+
 impl Callable for NativeMethod {
-    fn call(&self, _: &mut Interpreter, arguments: &Vec<Value>) -> Result<Value, Flow> {
+    fn call(&self, interpreter: &mut Interpreter, arguments: &[Value]) -> Result<Value, Flow> {
         let arity = arguments.len();
         if arity < self.min_arity || arity > self.max_arity {
             if self.min_arity != self.max_arity {
-                return Err(Flow::Error(format!(
+                return Err(Flow::error(format!(
                     "Expected ({}, {}] arguments but got {}",
                     self.min_arity,
                     self.max_arity,
@@ -38,13 +46,14 @@ impl Callable for NativeMethod {
                 )));
             }
 
-            return Err(Flow::Error(format!(
+            return Err(Flow::error(format!(
                 "Expected {} arguments but got {}",
-                arity,
+                self.min_arity,
                 arguments.len()
             )));
         }
 
-        (self.function)(&self.this.borrow(), arguments)
+        let this = self.this.borrow().clone();
+        (self.function)(interpreter, &this, arguments)
     }
 }
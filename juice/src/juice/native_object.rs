@@ -0,0 +1,225 @@
+use super::{flow::Flow, interpreter::Interpreter, native_method::NativeMethod, value::Value};
+use std::{any::Any, cell::RefCell, collections::HashMap, rc::Rc};
+
+pub type NativeConstructorFn =
+    dyn Fn(&mut Interpreter, &HashMap<String, Value>) -> Result<Box<dyn Any>, Flow>;
+pub type NativeObjectMethodFn =
+    dyn Fn(&mut dyn Any, &mut Interpreter, &[Value]) -> Result<Value, Flow>;
+pub type NativeGetterFn = dyn Fn(&dyn Any) -> Result<Value, Flow>;
+pub type NativeSetterFn = dyn Fn(&mut dyn Any, Value) -> Result<Value, Flow>;
+pub type NativeDisplayFn = dyn Fn(&dyn Any) -> String;
+pub type NativeEqFn = dyn Fn(&dyn Any, &dyn Any) -> bool;
+
+#[derive(Clone)]
+pub struct NativeObjectMethod {
+    pub function: Rc<NativeObjectMethodFn>,
+    pub min_arity: usize,
+    pub max_arity: usize,
+}
+
+/// Describes a host type that scripts can construct with `TypeName { ... }`.
+pub struct NativeType {
+    pub name: String,
+    pub traits: Vec<String>,
+    pub constructor: Rc<NativeConstructorFn>,
+    pub methods: HashMap<String, NativeObjectMethod>,
+    pub getters: HashMap<String, Rc<NativeGetterFn>>,
+    pub setters: HashMap<String, Rc<NativeSetterFn>>,
+    pub display: Option<Rc<NativeDisplayFn>>,
+    pub eq: Option<Rc<NativeEqFn>>,
+}
+
+impl NativeType {
+    pub fn new<T: 'static>(
+        name: &str,
+        constructor: impl Fn(&mut Interpreter, &HashMap<String, Value>) -> Result<T, Flow> + 'static,
+    ) -> Self {
+        Self {
+            name: name.to_string(),
+            traits: Vec::new(),
+            constructor: Rc::new(move |interpreter, fields| {
+                Ok(Box::new(constructor(interpreter, fields)?) as Box<dyn Any>)
+            }),
+            methods: HashMap::new(),
+            getters: HashMap::new(),
+            setters: HashMap::new(),
+            display: None,
+            eq: None,
+        }
+    }
+
+    pub fn with_trait(mut self, name: &str) -> Self {
+        self.traits.push(name.to_string());
+        self
+    }
+
+    pub fn with_method<T: 'static>(
+        self,
+        name: &str,
+        arity: usize,
+        function: impl Fn(&mut T, &mut Interpreter, &[Value]) -> Result<Value, Flow> + 'static,
+    ) -> Self {
+        self.with_variadic_method(name, arity, arity, function)
+    }
+
+    pub fn with_variadic_method<T: 'static>(
+        mut self,
+        name: &str,
+        min_arity: usize,
+        max_arity: usize,
+        function: impl Fn(&mut T, &mut Interpreter, &[Value]) -> Result<Value, Flow> + 'static,
+    ) -> Self {
+        let type_name = self.name.clone();
+        let function = move |data: &mut dyn Any,
+                             interpreter: &mut Interpreter,
+                             arguments: &[Value]| {
+            match data.downcast_mut::<T>() {
+                Some(data) => function(data, interpreter, arguments),
+                None => Err(mismatched_type(&type_name)),
+            }
+        };
+        self.methods.insert(
+            name.to_string(),
+            NativeObjectMethod {
+                function: Rc::new(function),
+                min_arity,
+                max_arity,
+            },
+        );
+        self
+    }
+
+    pub fn with_getter<T: 'static>(
+        mut self,
+        name: &str,
+        getter: impl Fn(&T) -> Result<Value, Flow> + 'static,
+    ) -> Self {
+        let type_name = self.name.clone();
+        self.getters.insert(
+            name.to_string(),
+            Rc::new(move |data: &dyn Any| match data.downcast_ref::<T>() {
+                Some(data) => getter(data),
+                None => Err(mismatched_type(&type_name)),
+            }),
+        );
+        self
+    }
+
+    pub fn with_setter<T: 'static>(
+        mut self,
+        name: &str,
+        setter: impl Fn(&mut T, Value) -> Result<Value, Flow> + 'static,
+    ) -> Self {
+        let type_name = self.name.clone();
+        self.setters.insert(
+            name.to_string(),
+            Rc::new(
+                move |data: &mut dyn Any, value| match data.downcast_mut::<T>() {
+                    Some(data) => setter(data, value),
+                    None => Err(mismatched_type(&type_name)),
+                },
+            ),
+        );
+        self
+    }
+
+    pub fn with_display<T: 'static>(mut self, display: impl Fn(&T) -> String + 'static) -> Self {
+        let type_name = self.name.clone();
+        self.display = Some(Rc::new(move |data: &dyn Any| {
+            match data.downcast_ref::<T>() {
+                Some(data) => display(data),
+                None => format!("<{}>", type_name),
+            }
+        }));
+        self
+    }
+
+    pub fn with_eq<T: 'static>(mut self, eq: impl Fn(&T, &T) -> bool + 'static) -> Self {
+        self.eq = Some(Rc::new(move |a: &dyn Any, b: &dyn Any| {
+            match (a.downcast_ref::<T>(), b.downcast_ref::<T>()) {
+                (Some(a), Some(b)) => eq(a, b),
+                _ => false,
+            }
+        }));
+        self
+    }
+}
+
+fn mismatched_type(type_name: &str) -> Flow {
+    Flow::error(format!("Native object is not of type {}", type_name))
+}
+
+/// An instance of a `NativeType`, holding the host state behind it.
+pub struct NativeObject {
+    pub native_type: Rc<NativeType>,
+    pub data: Box<dyn Any>,
+}
+
+impl NativeObject {
+    pub fn new(native_type: Rc<NativeType>, data: Box<dyn Any>) -> Self {
+        Self { native_type, data }
+    }
+
+    pub fn type_name(&self) -> &str {
+        &self.native_type.name
+    }
+
+    pub fn downcast_ref<T: 'static>(&self) -> Option<&T> {
+        self.data.downcast_ref::<T>()
+    }
+
+    pub fn downcast_mut<T: 'static>(&mut self) -> Option<&mut T> {
+        self.data.downcast_mut::<T>()
+    }
+
+    pub fn get_method(this: &Rc<RefCell<NativeObject>>, name: &str) -> Result<Value, Flow> {
+        let method = match this.borrow().native_type.methods.get(name) {
+            Some(method) => method.clone(),
+            None => return Err(Flow::error(format!("Method {} not found", name))),
+        };
+
+        let function = method.function.clone();
+        let native_method = NativeMethod::new(
+            move |interpreter, this, arguments| {
+                let object = this.as_native_object()?;
+                let mut object = object
+                    .try_borrow_mut()
+                    .map_err(|_| Flow::error("Native object is already in use".to_string()))?;
+                function(object.data.as_mut(), interpreter, arguments)
+            },
+            Rc::new(RefCell::new(Value::NativeObject(this.clone()))),
+            method.min_arity,
+            method.max_arity,
+        );
+        Ok(Value::new_native_method(native_method))
+    }
+
+    pub fn get_value(&self, name: &str) -> Result<Value, Flow> {
+        match self.native_type.getters.get(name) {
+            Some(getter) => getter(self.data.as_ref()),
+            None => Err(Flow::error(format!("Field {} not found", name))),
+        }
+    }
+
+    pub fn set_value(&mut self, name: &str, value: Value) -> Result<Value, Flow> {
+        match self.native_type.setters.get(name).cloned() {
+            Some(setter) => setter(self.data.as_mut(), value),
+            None => Err(Flow::error(format!("Field {} is not writable", name))),
+        }
+    }
+}
+
+impl PartialEq for NativeObject {
+    fn eq(&self, other: &Self) -> bool {
+        if std::ptr::eq(self, other) {
+            return true;
+        }
+        if !Rc::ptr_eq(&self.native_type, &other.native_type) {
+            return false;
+        }
+        match &self.native_type.eq {
+            Some(eq) => eq(self.data.as_ref(), other.data.as_ref()),
+            None => false,
+        }
+    }
+}
@@ -1,10 +1,44 @@
-use super::{flow::Flow, value::Value};
-use std::collections::HashMap;
+use super::{flow::Flow, symbol::Symbol, value::Value};
+use std::{
+    cell::RefCell,
+    collections::{HashMap, VecDeque},
+    mem,
+    rc::Rc,
+};
+
+/// Instances whose last reference went away and whose `deinit` has yet to run; each
+/// interpreter owns one, so only the interpreter that created an instance finalizes it.
+#[derive(Clone, Default)]
+pub struct DeinitQueue(Rc<RefCell<VecDeque<Object>>>);
+
+impl DeinitQueue {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Takes the next dropped instance waiting for its `deinit` to run.
+    pub fn pop(&self) -> Option<Object> {
+        self.0.borrow_mut().pop_front()
+    }
+
+    fn push(&self, object: Object) {
+        self.0.borrow_mut().push_back(object);
+    }
+}
+
+impl PartialEq for DeinitQueue {
+    fn eq(&self, other: &Self) -> bool {
+        Rc::ptr_eq(&self.0, &other.0)
+    }
+}
 
 #[derive(Clone, PartialEq)]
 pub struct Object {
-    pub values: HashMap<String, Value>,
-    pub methods: HashMap<String, Value>,
+    pub values: HashMap<Symbol, Value>,
+    pub methods: HashMap<Symbol, Value>,
+    pub prototype: Option<Rc<Object>>,
+    /// Set for instances with a `deinit` method: where they are queued once dropped.
+    pub deinit: Option<DeinitQueue>,
 }
 
 impl Object {
@@ -12,13 +46,31 @@ impl Object {
         Self {
             values: HashMap::new(),
             methods: HashMap::new(),
+            prototype: None,
+            deinit: None,
         }
     }
 
-    pub fn instantiate(&self) -> Self {
+    /// Creates an instance that looks its methods up in the shared `prototype`.
+    pub fn instantiate(prototype: &Rc<Object>) -> Self {
         Self {
             values: HashMap::new(),
-            methods: self.methods.clone(),
+            methods: HashMap::new(),
+            prototype: Some(prototype.clone()),
+            deinit: None,
+        }
+    }
+
+    /// Looks up a method and binds it to `this`, so prototypes can share unbound methods.
+    pub fn get_bound_method(this: &Rc<RefCell<Object>>, name: &str) -> Result<Value, Flow> {
+        let method = this.borrow().get_method(name)?;
+        match &method {
+            Value::Method(unbound) if unbound.borrow().this.is_none() => {
+                let mut bound = unbound.borrow().clone();
+                bound.bind(this.clone());
+                Ok(Value::new_method(bound))
+            }
+            _ => Ok(method),
         }
     }
 
@@ -26,12 +78,15 @@ impl Object {
         if let Some(value) = self.methods.get(name) {
             return Ok(value.clone());
         }
+        if let Some(prototype) = &self.prototype {
+            return prototype.get_method(name);
+        }
 
-        Err(Flow::Error(format!("Method {} not found", name)))
+        Err(Flow::error(format!("Method {} not found", name)))
     }
 
-    pub fn define_method(&mut self, name: String, method: Value) -> Result<Value, Flow> {
-        self.methods.insert(name, method);
+    pub fn define_method(&mut self, name: impl Into<Symbol>, method: Value) -> Result<Value, Flow> {
+        self.methods.insert(name.into(), method);
         Ok(Value::Void)
     }
 
@@ -40,24 +95,43 @@ impl Object {
             return Ok(value.clone());
         }
 
-        Err(Flow::Error(format!("Field {} not found", name)))
+        Err(Flow::error(format!("Field {} not found", name)))
     }
 
-    pub fn define_value(&mut self, name: String, value: Value) -> Result<Value, Flow> {
+    pub fn define_value(&mut self, name: impl Into<Symbol>, value: Value) -> Result<Value, Flow> {
+        let name = name.into();
         if self.values.contains_key(&name) {
-            return Err(Flow::Error(format!("Field {} already defined", name)));
+            return Err(Flow::error(format!("Field {} already defined", name)));
         }
 
         self.values.insert(name, value);
         Ok(Value::Void)
     }
 
-    pub fn set_value(&mut self, name: String, value: Value) -> Result<Value, Flow> {
-        if self.values.contains_key(&name) {
-            self.values.insert(name, value);
+    pub fn set_value(&mut self, name: &str, value: Value) -> Result<Value, Flow> {
+        if let Some(slot) = self.values.get_mut(name) {
+            *slot = value;
             return Ok(Value::Void);
         }
 
-        Err(Flow::Error(format!("Variable {} not found", name)))
+        Err(Flow::error(format!("Variable {} not found", name)))
+    }
+}
+
+impl Drop for Object {
+    fn drop(&mut self) {
+        let Some(deinit) = self.deinit.take() else {
+            return;
+        };
+
+        // Script code cannot run from inside `drop`, so the fields move into a fresh
+        // instance that the interpreter finalizes later. That instance has no queue of
+        // its own, so `deinit` runs at most once even if it resurrects `this`.
+        deinit.push(Object {
+            values: mem::take(&mut self.values),
+            methods: mem::take(&mut self.methods),
+            prototype: self.prototype.take(),
+            deinit: None,
+        });
     }
 }
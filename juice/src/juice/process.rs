@@ -0,0 +1,35 @@
+use super::{
+    flow::Flow, interpreter::Interpreter, native_function::NativeFunction, object::Object,
+    value::Value,
+};
+
+/// Builds the `args` array from the arguments the host passes on to the script.
+pub fn arguments(args: impl IntoIterator<Item = impl Into<String>>) -> Result<Value, Flow> {
+    let args = args
+        .into_iter()
+        .map(|arg| Value::new_string(arg.into()))
+        .collect();
+    Value::new_array(args)
+}
+
+/// Builds the `env` namespace object that `with_env` exposes to scripts.
+pub fn environment() -> Result<Value, Flow> {
+    let mut env = Object::new();
+    env.define_method(
+        "get",
+        Value::new_native_function(NativeFunction::new(get, 1, 1)),
+    )?;
+    Ok(Value::new_object(env))
+}
+
+// Unset variables and ones that are not valid unicode both read as `null`.
+fn get(interpreter: &mut Interpreter, values: &[Value]) -> Result<Value, Flow> {
+    let name = values[0].as_string()?.to_string();
+    match std::env::var(name) {
+        Ok(value) => {
+            interpreter.budget.allocate(value.len())?;
+            Ok(Value::new_string(value))
+        }
+        Err(_) => Ok(Value::Null),
+    }
+}
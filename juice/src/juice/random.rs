@@ -0,0 +1,130 @@
+use super::{flow::Flow, interpreter::Interpreter, native_object::NativeType, value::Value};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// A xoshiro256** generator whose state is expanded from a 64-bit seed with SplitMix64,
+/// so the same seed yields the same sequence on every platform.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Random {
+    state: [u64; 4],
+}
+
+impl Random {
+    pub fn new(seed: u64) -> Self {
+        let mut random = Self { state: [0; 4] };
+        random.seed(seed);
+        random
+    }
+
+    /// Seeds from the system clock, for runs that do not ask for reproducibility.
+    pub fn from_time() -> Self {
+        let nanos = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|duration| duration.as_nanos() as u64)
+            .unwrap_or_default();
+        Self::new(nanos)
+    }
+
+    pub fn seed(&mut self, seed: u64) {
+        let mut x = seed;
+        for word in &mut self.state {
+            x = x.wrapping_add(0x9e37_79b9_7f4a_7c15);
+            let mut z = x;
+            z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+            z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+            *word = z ^ (z >> 31);
+        }
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        let [s0, s1, s2, s3] = &mut self.state;
+        let result = s1.wrapping_mul(5).rotate_left(7).wrapping_mul(9);
+        let t = *s1 << 17;
+        *s2 ^= *s0;
+        *s3 ^= *s1;
+        *s1 ^= *s2;
+        *s0 ^= *s3;
+        *s2 ^= t;
+        *s3 = s3.rotate_left(45);
+        result
+    }
+
+    /// A float in `[0, 1)` built from the top 53 bits.
+    pub fn next_float(&mut self) -> f64 {
+        (self.next_u64() >> 11) as f64 / (1u64 << 53) as f64
+    }
+
+    /// An integer in `[low, high)`, without modulo bias.
+    pub fn next_int(&mut self, low: i64, high: i64) -> Result<i64, Flow> {
+        if low >= high {
+            return Err(Flow::error(format!("Invalid range {} to {}", low, high)));
+        }
+        let span = high.wrapping_sub(low) as u64;
+        Ok(low.wrapping_add(self.below(span) as i64))
+    }
+
+    // Lemire's multiply-and-reject method.
+    fn below(&mut self, bound: u64) -> u64 {
+        let threshold = bound.wrapping_neg() % bound;
+        loop {
+            let product = self.next_u64() as u128 * bound as u128;
+            if product as u64 >= threshold {
+                return (product >> 64) as u64;
+            }
+        }
+    }
+}
+
+/// The `Random` native type; `Random {}` draws its seed from the default `random` generator.
+pub fn native_type() -> NativeType {
+    NativeType::new("Random", |interpreter, fields| {
+        if let Some(name) = fields.keys().find(|name| *name != "seed") {
+            return Err(Flow::error(format!("Unexpected field {} for Random", name)));
+        }
+        match fields.get("seed") {
+            Some(seed) => Ok(Random::new(seed.as_integer()? as u64)),
+            None => Ok(Random::new(default_seed(interpreter))),
+        }
+    })
+    .with_method("seed", 1, |random: &mut Random, _, arguments| {
+        random.seed(arguments[0].as_integer()? as u64);
+        Ok(Value::Void)
+    })
+    .with_method("nextFloat", 0, |random: &mut Random, _, _| {
+        Ok(Value::Number(random.next_float()))
+    })
+    .with_method("nextInt", 2, |random: &mut Random, _, arguments| {
+        let low = arguments[0].as_integer()?;
+        let high = arguments[1].as_integer()?;
+        Ok(Value::Integer(random.next_int(low, high)?))
+    })
+    .with_method("choice", 1, |random: &mut Random, _, arguments| {
+        let array = arguments[0].as_array()?;
+        let array = array.borrow();
+        if array.elements.is_empty() {
+            return Err(Flow::error("Cannot choose from an empty array".to_string()));
+        }
+        let index = random.below(array.elements.len() as u64) as usize;
+        Ok(array.elements[index].clone())
+    })
+    .with_method("shuffle", 1, |random: &mut Random, _, arguments| {
+        let array = arguments[0].as_array()?;
+        let mut array = array.borrow_mut();
+        for i in (1..array.elements.len()).rev() {
+            let j = random.below(i as u64 + 1) as usize;
+            array.elements.swap(i, j);
+        }
+        Ok(Value::Void)
+    })
+}
+
+fn default_seed(interpreter: &Interpreter) -> u64 {
+    let random = interpreter.env.prelude().borrow().get_value("random");
+    if let Ok(Value::NativeObject(random)) = random {
+        if let Ok(mut random) = random.try_borrow_mut() {
+            if let Some(random) = random.downcast_mut::<Random>() {
+                return random.next_u64();
+            }
+        }
+    }
+    Random::from_time().next_u64()
+}
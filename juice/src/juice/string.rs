@@ -0,0 +1,153 @@
+use std::{
+    cell::{Ref, RefCell},
+    fmt::{Debug, Display},
+    ops::Deref,
+    rc::Rc,
+};
+
+/// An immutable script string that is cheap to clone.
+///
+/// Strings built by concatenation share a growable buffer and only view a prefix of it.
+/// Appending to a string that still ends its buffer extends that buffer in place, so a
+/// loop like `line = line + "*"` stays linear; older views are unaffected because they
+/// never look past their own length.
+#[derive(Clone)]
+pub enum Str {
+    Shared(Rc<str>),
+    Buffer {
+        buffer: Rc<RefCell<String>>,
+        len: usize,
+    },
+}
+
+pub enum StrRef<'a> {
+    Shared(&'a str),
+    Buffer(Ref<'a, str>),
+}
+
+impl Deref for StrRef<'_> {
+    type Target = str;
+
+    fn deref(&self) -> &str {
+        match self {
+            StrRef::Shared(s) => s,
+            StrRef::Buffer(s) => s,
+        }
+    }
+}
+
+impl Str {
+    pub fn as_str(&self) -> StrRef<'_> {
+        match self {
+            Str::Shared(s) => StrRef::Shared(s),
+            Str::Buffer { buffer, len } => {
+                StrRef::Buffer(Ref::map(buffer.borrow(), |s| &s[..*len]))
+            }
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        match self {
+            Str::Shared(s) => s.len(),
+            Str::Buffer { len, .. } => *len,
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn ptr_eq(&self, other: &Str) -> bool {
+        match (self, other) {
+            (Str::Shared(a), Str::Shared(b)) => Rc::ptr_eq(a, b),
+            (
+                Str::Buffer {
+                    buffer: a,
+                    len: a_len,
+                },
+                Str::Buffer {
+                    buffer: b,
+                    len: b_len,
+                },
+            ) => Rc::ptr_eq(a, b) && a_len == b_len,
+            _ => false,
+        }
+    }
+
+    /// Whether `self` was built by appending to `prefix` in place.
+    pub fn extends(&self, prefix: &Str) -> bool {
+        match (self, prefix) {
+            (Str::Buffer { buffer: a, .. }, Str::Buffer { buffer: b, .. }) => Rc::ptr_eq(a, b),
+            _ => false,
+        }
+    }
+
+    pub fn concat(&self, other: &Str) -> Str {
+        if let Str::Buffer { buffer, len } = self {
+            let same_buffer =
+                matches!(other, Str::Buffer { buffer: other, .. } if Rc::ptr_eq(buffer, other));
+            if !same_buffer {
+                // Only the newest view of a buffer may extend it.
+                if let Ok(mut string) = buffer.try_borrow_mut() {
+                    if string.len() == *len {
+                        string.push_str(&other.as_str());
+                        return Str::Buffer {
+                            buffer: buffer.clone(),
+                            len: string.len(),
+                        };
+                    }
+                }
+            }
+        }
+
+        let mut string = String::with_capacity(self.len() + other.len());
+        string.push_str(&self.as_str());
+        string.push_str(&other.as_str());
+        Str::from(string)
+    }
+}
+
+impl From<String> for Str {
+    fn from(string: String) -> Self {
+        Str::Buffer {
+            len: string.len(),
+            buffer: Rc::new(RefCell::new(string)),
+        }
+    }
+}
+
+impl From<&str> for Str {
+    fn from(string: &str) -> Self {
+        Str::Shared(Rc::from(string))
+    }
+}
+
+impl From<Rc<str>> for Str {
+    fn from(string: Rc<str>) -> Self {
+        Str::Shared(string)
+    }
+}
+
+impl PartialEq for Str {
+    fn eq(&self, other: &Self) -> bool {
+        self.ptr_eq(other) || *self.as_str() == *other.as_str()
+    }
+}
+
+impl PartialOrd for Str {
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        self.as_str().partial_cmp(&*other.as_str())
+    }
+}
+
+impl Display for Str {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", &*self.as_str())
+    }
+}
+
+impl Debug for Str {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:?}", &*self.as_str())
+    }
+}
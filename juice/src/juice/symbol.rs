@@ -0,0 +1,23 @@
+use std::{cell::RefCell, collections::HashSet, rc::Rc};
+
+/// An interned name: every identifier spelled the same way shares one allocation, so
+/// cloning it into scopes and objects is a reference count bump.
+pub type Symbol = Rc<str>;
+
+thread_local! {
+    static SYMBOLS: RefCell<HashSet<Rc<str>>> = RefCell::new(HashSet::new());
+}
+
+pub fn intern(name: &str) -> Symbol {
+    SYMBOLS.with(|symbols| {
+        let mut symbols = symbols.borrow_mut();
+        match symbols.get(name) {
+            Some(symbol) => symbol.clone(),
+            None => {
+                let symbol: Symbol = Rc::from(name);
+                symbols.insert(symbol.clone());
+                symbol
+            }
+        }
+    })
+}
@@ -0,0 +1,220 @@
+use super::{
+    flow::Flow, interpreter::Interpreter, native_function::NativeFunction,
+    native_object::NativeType, object::Object, value::Value,
+};
+use std::{
+    cell::Cell,
+    rc::Rc,
+    thread,
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
+};
+
+// Long sleeps on a blocking clock are split up so that interrupts and timeouts still stop
+// the script promptly.
+const SLEEP_SLICE: Duration = Duration::from_millis(10);
+
+/// Where scripts read the time from; swap in a `ManualClock` to run time-based scripts deterministically.
+pub trait Clock {
+    /// Monotonic time since an arbitrary fixed point.
+    fn now(&self) -> Duration;
+    /// Time since the Unix epoch.
+    fn wall_clock(&self) -> Duration;
+    fn sleep(&self, duration: Duration);
+    /// Whether `sleep` blocks the thread; a clock that only moves time forward sleeps in one go.
+    fn blocks(&self) -> bool {
+        true
+    }
+}
+
+/// The real clock, used unless the host installs another one with `with_clock`.
+#[derive(Debug, Clone)]
+pub struct SystemClock {
+    start: Instant,
+}
+
+impl SystemClock {
+    pub fn new() -> Self {
+        Self {
+            start: Instant::now(),
+        }
+    }
+}
+
+impl Default for SystemClock {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Clock for SystemClock {
+    fn now(&self) -> Duration {
+        self.start.elapsed()
+    }
+
+    fn wall_clock(&self) -> Duration {
+        SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+    }
+
+    fn sleep(&self, duration: Duration) {
+        thread::sleep(duration);
+    }
+}
+
+/// A clock that only moves when told to, or when a script sleeps; clones share the same time.
+#[derive(Debug, Clone, Default)]
+pub struct ManualClock {
+    elapsed: Rc<Cell<Duration>>,
+    epoch: Duration,
+}
+
+impl ManualClock {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Makes `wallClock()` start at `since_epoch` instead of the Unix epoch itself.
+    pub fn with_wall_clock(mut self, since_epoch: Duration) -> Self {
+        self.epoch = since_epoch;
+        self
+    }
+
+    pub fn advance(&self, duration: Duration) {
+        self.elapsed
+            .set(self.elapsed.get().saturating_add(duration));
+    }
+}
+
+impl Clock for ManualClock {
+    fn now(&self) -> Duration {
+        self.elapsed.get()
+    }
+
+    fn wall_clock(&self) -> Duration {
+        self.epoch.saturating_add(self.elapsed.get())
+    }
+
+    fn sleep(&self, duration: Duration) {
+        self.advance(duration);
+    }
+
+    fn blocks(&self) -> bool {
+        false
+    }
+}
+
+/// Builds the `time` namespace object that `with_std` exposes to scripts.
+pub fn module() -> Result<Value, Flow> {
+    let mut time = Object::new();
+    time.define_method(
+        "now",
+        Value::new_native_function(NativeFunction::new(now, 0, 0)),
+    )?;
+    time.define_method(
+        "wallClock",
+        Value::new_native_function(NativeFunction::new(wall_clock, 0, 0)),
+    )?;
+    time.define_method(
+        "sleep",
+        Value::new_native_function(NativeFunction::new(sleep, 1, 1)),
+    )?;
+    Ok(Value::new_object(time))
+}
+
+fn now(interpreter: &mut Interpreter, _: &[Value]) -> Result<Value, Flow> {
+    Ok(Value::Number(interpreter.clock.now().as_secs_f64()))
+}
+
+fn wall_clock(interpreter: &mut Interpreter, _: &[Value]) -> Result<Value, Flow> {
+    Ok(Value::Number(interpreter.clock.wall_clock().as_secs_f64()))
+}
+
+fn sleep(interpreter: &mut Interpreter, values: &[Value]) -> Result<Value, Flow> {
+    let milliseconds = values[0].as_number()?;
+    let duration = match Duration::try_from_secs_f64(milliseconds / 1000.0) {
+        Ok(duration) => duration,
+        Err(_) => {
+            return Err(Flow::error(format!(
+                "Cannot sleep for {} milliseconds",
+                values[0]
+            )))
+        }
+    };
+
+    let clock = interpreter.clock.clone();
+    if !clock.blocks() {
+        interpreter.budget.check_interrupt()?;
+        clock.sleep(duration);
+        return Ok(Value::Void);
+    }
+
+    let wake = clock.now().saturating_add(duration);
+    loop {
+        interpreter.budget.check_interrupt()?;
+        let remaining = wake.saturating_sub(clock.now());
+        if remaining.is_zero() {
+            return Ok(Value::Void);
+        }
+        clock.sleep(remaining.min(SLEEP_SLICE));
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Stopwatch {
+    elapsed: Duration,
+    started: Option<Duration>,
+}
+
+impl Stopwatch {
+    fn elapsed(&self, clock: &dyn Clock) -> Duration {
+        match self.started {
+            Some(started) => self.elapsed + clock.now().saturating_sub(started),
+            None => self.elapsed,
+        }
+    }
+}
+
+/// The `Stopwatch` native type; `Stopwatch {}` starts running immediately.
+pub fn stopwatch_type() -> NativeType {
+    NativeType::new("Stopwatch", |interpreter, fields| {
+        if let Some(name) = fields.keys().next() {
+            return Err(Flow::error(format!(
+                "Unexpected field {} for Stopwatch",
+                name
+            )));
+        }
+        Ok(Stopwatch {
+            elapsed: Duration::ZERO,
+            started: Some(interpreter.clock.now()),
+        })
+    })
+    .with_method("start", 0, |stopwatch: &mut Stopwatch, interpreter, _| {
+        if stopwatch.started.is_none() {
+            stopwatch.started = Some(interpreter.clock.now());
+        }
+        Ok(Value::Void)
+    })
+    .with_method("stop", 0, |stopwatch: &mut Stopwatch, interpreter, _| {
+        stopwatch.elapsed = stopwatch.elapsed(interpreter.clock.as_ref());
+        stopwatch.started = None;
+        Ok(Value::Void)
+    })
+    .with_method("reset", 0, |stopwatch: &mut Stopwatch, _, _| {
+        stopwatch.elapsed = Duration::ZERO;
+        stopwatch.started = None;
+        Ok(Value::Void)
+    })
+    .with_method("restart", 0, |stopwatch: &mut Stopwatch, interpreter, _| {
+        stopwatch.elapsed = Duration::ZERO;
+        stopwatch.started = Some(interpreter.clock.now());
+        Ok(Value::Void)
+    })
+    .with_method("elapsed", 0, |stopwatch: &mut Stopwatch, interpreter, _| {
+        let elapsed = stopwatch.elapsed(interpreter.clock.as_ref());
+        Ok(Value::Number(elapsed.as_secs_f64()))
+    })
+    .with_method("isRunning", 0, |stopwatch: &mut Stopwatch, _, _| {
+        Ok(Value::Bool(stopwatch.started.is_some()))
+    })
+}
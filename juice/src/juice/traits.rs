@@ -1,5 +1,5 @@
 use super::{flow::Flow, interpreter::Interpreter, value::Value};
 
 pub trait Callable {
-    fn call(&self, interpreter: &mut Interpreter, arguments: &Vec<Value>) -> Result<Value, Flow>;
+    fn call(&self, interpreter: &mut Interpreter, arguments: &[Value]) -> Result<Value, Flow>;
 }
@@ -1,6 +1,6 @@
 use super::{
-    array::Array, flow::Flow, method::Method, native_function::NativeFunction,
-    native_method::NativeMethod, object::Object,
+    array::Array, flow::Flow, gc, method::Method, native_function::NativeFunction,
+    native_method::NativeMethod, native_object::NativeObject, object::Object, string::Str,
 };
 use std::{
     cell::RefCell,
@@ -8,28 +8,31 @@ use std::{
     rc::Rc,
 };
 
-#[derive(Clone, PartialEq)]
+#[derive(Clone)]
 pub enum Value {
     Number(f64),
+    Integer(i64),
     Bool(bool),
-    String(Rc<RefCell<String>>),
+    String(Str),
     Object(Rc<RefCell<Object>>),
     Method(Rc<RefCell<Method>>),
     NativeMethod(Rc<RefCell<NativeMethod>>),
     NativeFunction(Rc<RefCell<NativeFunction>>),
     Array(Rc<RefCell<Array>>),
+    NativeObject(Rc<RefCell<NativeObject>>),
     Null,
     Void,
 }
 
 impl Value {
     pub fn new_string(string: String) -> Self {
-        let string = Rc::new(RefCell::new(string));
-        Value::String(string)
+        Value::String(Str::from(string))
     }
 
     pub fn new_object(object: Object) -> Self {
-        Value::Object(Rc::new(RefCell::new(object)))
+        let object = Rc::new(RefCell::new(object));
+        gc::track_object(&object);
+        Value::Object(object)
     }
 
     pub fn new_method(method: Method) -> Self {
@@ -44,30 +47,91 @@ impl Value {
         Value::NativeFunction(Rc::new(RefCell::new(native_function)))
     }
 
+    pub fn new_native_object(native_object: NativeObject) -> Self {
+        Value::NativeObject(Rc::new(RefCell::new(native_object)))
+    }
+
     pub fn new_array(array: Vec<Value>) -> Result<Value, Flow> {
         let array = Rc::new(RefCell::new(Array::new(array)));
-        let value = Value::Array(array.clone());
-
-        array
-            .borrow_mut()
-            .wrap(Rc::new(RefCell::new(value.clone())))?;
+        gc::track_array(&array);
+        Ok(Value::Array(array))
+    }
 
-        Ok(value)
+    pub fn type_name(&self) -> String {
+        match self {
+            Value::Number(_) => "number".to_string(),
+            Value::Integer(_) => "integer".to_string(),
+            Value::Bool(_) => "bool".to_string(),
+            Value::String(_) => "string".to_string(),
+            Value::Object(_) => "object".to_string(),
+            Value::Method(_) => "method".to_string(),
+            Value::NativeMethod(_) => "native method".to_string(),
+            Value::NativeFunction(_) => "native function".to_string(),
+            Value::Array(_) => "array".to_string(),
+            Value::NativeObject(object) => match object.try_borrow() {
+                Ok(object) => object.type_name().to_string(),
+                Err(_) => "native object".to_string(),
+            },
+            Value::Null => "null".to_string(),
+            Value::Void => "void".to_string(),
+        }
     }
 
     pub fn as_number(&self) -> Result<f64, Flow> {
         match self {
             Value::Number(n) => Ok(*n),
-            _ => Err(Flow::Error(
+            Value::Integer(n) => Ok(*n as f64),
+            _ => Err(Flow::error(
                 "Invalid operands for number operation".to_string(),
             )),
         }
     }
 
-    pub fn as_string(&self) -> Result<Rc<RefCell<String>>, Flow> {
+    /// Accepts integers and whole floats; fractional values are rejected rather than truncated.
+    pub fn as_integer(&self) -> Result<i64, Flow> {
+        match self {
+            Value::Integer(n) => Ok(*n),
+            Value::Number(n)
+                if n.fract() == 0.0 && *n >= i64::MIN as f64 && *n < i64::MAX as f64 =>
+            {
+                Ok(*n as i64)
+            }
+            Value::Number(n) => Err(Flow::error(format!("Expected integer but got {}", n))),
+            _ => Err(Flow::error(format!(
+                "Expected integer but got {}",
+                self.type_name()
+            ))),
+        }
+    }
+
+    fn as_float(&self) -> Option<f64> {
+        match self {
+            Value::Number(n) => Some(*n),
+            Value::Integer(n) => Some(*n as f64),
+            _ => None,
+        }
+    }
+
+    // Mixing an integer with a float promotes both operands to floats.
+    fn float_operation(
+        &self,
+        rhs: &Value,
+        operation: &str,
+        apply: fn(f64, f64) -> Value,
+    ) -> Result<Value, Flow> {
+        match (self.as_float(), rhs.as_float()) {
+            (Some(a), Some(b)) => Ok(apply(a, b)),
+            _ => Err(Flow::error(format!(
+                "Invalid operands for {} operation",
+                operation
+            ))),
+        }
+    }
+
+    pub fn as_string(&self) -> Result<Str, Flow> {
         match self {
             Value::String(s) => Ok(s.clone()),
-            _ => Err(Flow::Error(
+            _ => Err(Flow::error(
                 "Invalid operands for string operation".to_string(),
             )),
         }
@@ -76,7 +140,7 @@ impl Value {
     pub fn as_bool(&self) -> Result<bool, Flow> {
         match self {
             Value::Bool(b) => Ok(*b),
-            _ => Err(Flow::Error(
+            _ => Err(Flow::error(
                 "Invalid operands for boolean operation".to_string(),
             )),
         }
@@ -85,7 +149,7 @@ impl Value {
     pub fn as_object(&self) -> Result<Rc<RefCell<Object>>, Flow> {
         match self {
             Value::Object(o) => Ok(o.clone()),
-            _ => Err(Flow::Error(
+            _ => Err(Flow::error(
                 "Invalid operands for object operation".to_string(),
             )),
         }
@@ -94,16 +158,25 @@ impl Value {
     pub fn as_array(&self) -> Result<Rc<RefCell<Array>>, Flow> {
         match self {
             Value::Array(a) => Ok(a.clone()),
-            _ => Err(Flow::Error(
+            _ => Err(Flow::error(
                 "Invalid operands for array operation".to_string(),
             )),
         }
     }
 
+    pub fn as_native_object(&self) -> Result<Rc<RefCell<NativeObject>>, Flow> {
+        match self {
+            Value::NativeObject(o) => Ok(o.clone()),
+            _ => Err(Flow::error(
+                "Invalid operands for native object operation".to_string(),
+            )),
+        }
+    }
+
     pub fn as_method(&self) -> Result<Rc<RefCell<Method>>, Flow> {
         match self {
             Value::Method(m) => Ok(m.clone()),
-            _ => Err(Flow::Error(
+            _ => Err(Flow::error(
                 "Invalid operands for method operation".to_string(),
             )),
         }
@@ -133,7 +206,7 @@ impl Value {
     pub fn is_truthy(&self) -> Result<bool, Flow> {
         match self {
             Value::Bool(b) => Ok(*b),
-            _ => Err(Flow::Error(
+            _ => Err(Flow::error(
                 "Invalid operands for boolean operation".to_string(),
             )),
         }
@@ -151,10 +224,20 @@ impl Value {
         Ok(Value::Bool(self.is_truthy()?))
     }
 
+    pub fn bit_not(&self) -> Result<Value, Flow> {
+        match self {
+            Value::Integer(n) => Ok(Value::Integer(!n)),
+            _ => Err(Flow::error(
+                "Invalid operands for bitwise not operation".to_string(),
+            )),
+        }
+    }
+
     pub fn neg(&self) -> Result<Value, Flow> {
         match self {
             Value::Number(n) => Ok(Value::Number(-n)),
-            _ => Err(Flow::Error(
+            Value::Integer(n) => checked(n.checked_neg()),
+            _ => Err(Flow::error(
                 "Invalid operands for negation operation".to_string(),
             )),
         }
@@ -162,54 +245,90 @@ impl Value {
 
     pub fn add(&self, rhs: &Value) -> Result<Value, Flow> {
         match (self, rhs) {
-            (Value::Number(a), Value::Number(b)) => Ok(Value::Number(a + b)),
-            (Value::String(a), Value::String(b)) => Ok(Value::String(Rc::new(RefCell::new(
-                format!("{}{}", a.borrow(), b.borrow()),
-            )))),
-            _ => Err(Flow::Error(
-                "Invalid operands for add operation".to_string(),
-            )),
+            (Value::Integer(a), Value::Integer(b)) => checked(a.checked_add(*b)),
+            (Value::String(a), Value::String(b)) => Ok(Value::String(a.concat(b))),
+            _ => self.float_operation(rhs, "add", |a, b| Value::Number(a + b)),
         }
     }
 
     pub fn sub(&self, rhs: &Value) -> Result<Value, Flow> {
         match (self, rhs) {
-            (Value::Number(a), Value::Number(b)) => Ok(Value::Number(a - b)),
-            _ => Err(Flow::Error(
-                "Invalid operands for subtraction operation".to_string(),
-            )),
+            (Value::Integer(a), Value::Integer(b)) => checked(a.checked_sub(*b)),
+            _ => self.float_operation(rhs, "subtraction", |a, b| Value::Number(a - b)),
         }
     }
 
     pub fn mul(&self, rhs: &Value) -> Result<Value, Flow> {
         match (self, rhs) {
-            (Value::Number(a), Value::Number(b)) => Ok(Value::Number(a * b)),
-            _ => Err(Flow::Error(
-                "Invalid operands for multiplication operation".to_string(),
-            )),
+            (Value::Integer(a), Value::Integer(b)) => checked(a.checked_mul(*b)),
+            _ => self.float_operation(rhs, "multiplication", |a, b| Value::Number(a * b)),
         }
     }
 
     pub fn div(&self, rhs: &Value) -> Result<Value, Flow> {
         match (self, rhs) {
-            (Value::Number(a), Value::Number(b)) => {
-                if *b == 0.0 {
-                    Err(Flow::Error("Division by zero".to_string()))
-                } else {
-                    Ok(Value::Number(a / b))
-                }
+            // Dividing two integers truncates toward zero.
+            (Value::Integer(_), Value::Integer(0)) => {
+                Err(Flow::error("Division by zero".to_string()))
             }
-            _ => Err(Flow::Error(
-                "Invalid operands for division operation".to_string(),
-            )),
+            (Value::Integer(a), Value::Integer(b)) => checked(a.checked_div(*b)),
+            _ if rhs.as_float() == Some(0.0) => Err(Flow::error("Division by zero".to_string())),
+            _ => self.float_operation(rhs, "division", |a, b| Value::Number(a / b)),
         }
     }
 
     pub fn rem(&self, rhs: &Value) -> Result<Value, Flow> {
         match (self, rhs) {
-            (Value::Number(a), Value::Number(b)) => Ok(Value::Number(a % b)),
-            _ => Err(Flow::Error(
-                "Invalid operands for remainder operation".to_string(),
+            (Value::Integer(_), Value::Integer(0)) => {
+                Err(Flow::error("Division by zero".to_string()))
+            }
+            (Value::Integer(a), Value::Integer(b)) => checked(a.checked_rem(*b)),
+            _ => self.float_operation(rhs, "remainder", |a, b| Value::Number(a % b)),
+        }
+    }
+
+    pub fn bit_and(&self, rhs: &Value) -> Result<Value, Flow> {
+        match (self, rhs) {
+            (Value::Integer(a), Value::Integer(b)) => Ok(Value::Integer(a & b)),
+            _ => Err(Flow::error(
+                "Invalid operands for bitwise and operation".to_string(),
+            )),
+        }
+    }
+
+    pub fn bit_or(&self, rhs: &Value) -> Result<Value, Flow> {
+        match (self, rhs) {
+            (Value::Integer(a), Value::Integer(b)) => Ok(Value::Integer(a | b)),
+            _ => Err(Flow::error(
+                "Invalid operands for bitwise or operation".to_string(),
+            )),
+        }
+    }
+
+    pub fn bit_xor(&self, rhs: &Value) -> Result<Value, Flow> {
+        match (self, rhs) {
+            (Value::Integer(a), Value::Integer(b)) => Ok(Value::Integer(a ^ b)),
+            _ => Err(Flow::error(
+                "Invalid operands for bitwise xor operation".to_string(),
+            )),
+        }
+    }
+
+    pub fn shl(&self, rhs: &Value) -> Result<Value, Flow> {
+        match (self, rhs) {
+            (Value::Integer(a), Value::Integer(b)) => Ok(Value::Integer(a << shift_amount(*b)?)),
+            _ => Err(Flow::error(
+                "Invalid operands for shift left operation".to_string(),
+            )),
+        }
+    }
+
+    // Shifting right is arithmetic, so negative values keep their sign.
+    pub fn shr(&self, rhs: &Value) -> Result<Value, Flow> {
+        match (self, rhs) {
+            (Value::Integer(a), Value::Integer(b)) => Ok(Value::Integer(a >> shift_amount(*b)?)),
+            _ => Err(Flow::error(
+                "Invalid operands for shift right operation".to_string(),
             )),
         }
     }
@@ -224,41 +343,120 @@ impl Value {
 
     pub fn lt(&self, rhs: &Value) -> Result<Value, Flow> {
         match (self, rhs) {
-            (Value::Number(a), Value::Number(b)) => Ok(Value::Bool(a < b)),
+            (Value::Integer(a), Value::Integer(b)) => Ok(Value::Bool(a < b)),
             (Value::String(a), Value::String(b)) => Ok(Value::Bool(a < b)),
-            _ => Err(Flow::Error(
-                "Invalid operands for less than operation".to_string(),
-            )),
+            _ => self.float_operation(rhs, "less than", |a, b| Value::Bool(a < b)),
         }
     }
 
     pub fn gt(&self, rhs: &Value) -> Result<Value, Flow> {
         match (self, rhs) {
-            (Value::Number(a), Value::Number(b)) => Ok(Value::Bool(a > b)),
+            (Value::Integer(a), Value::Integer(b)) => Ok(Value::Bool(a > b)),
             (Value::String(a), Value::String(b)) => Ok(Value::Bool(a > b)),
-            _ => Err(Flow::Error(
-                "Invalid operands for greater than operation".to_string(),
-            )),
+            _ => self.float_operation(rhs, "greater than", |a, b| Value::Bool(a > b)),
         }
     }
 
     pub fn le(&self, rhs: &Value) -> Result<Value, Flow> {
         match (self, rhs) {
-            (Value::Number(a), Value::Number(b)) => Ok(Value::Bool(a <= b)),
+            (Value::Integer(a), Value::Integer(b)) => Ok(Value::Bool(a <= b)),
             (Value::String(a), Value::String(b)) => Ok(Value::Bool(a <= b)),
-            _ => Err(Flow::Error(
-                "Invalid operands for less than or equal operation".to_string(),
-            )),
+            _ => self.float_operation(rhs, "less than or equal", |a, b| Value::Bool(a <= b)),
         }
     }
 
     pub fn ge(&self, rhs: &Value) -> Result<Value, Flow> {
         match (self, rhs) {
-            (Value::Number(a), Value::Number(b)) => Ok(Value::Bool(a >= b)),
+            (Value::Integer(a), Value::Integer(b)) => Ok(Value::Bool(a >= b)),
             (Value::String(a), Value::String(b)) => Ok(Value::Bool(a >= b)),
-            _ => Err(Flow::Error(
-                "Invalid operands for greater than or equal operation".to_string(),
-            )),
+            _ => self.float_operation(rhs, "greater than or equal", |a, b| Value::Bool(a >= b)),
+        }
+    }
+}
+
+fn checked(result: Option<i64>) -> Result<Value, Flow> {
+    match result {
+        Some(n) => Ok(Value::Integer(n)),
+        None => Err(Flow::error("Integer overflow".to_string())),
+    }
+}
+
+fn shift_amount(amount: i64) -> Result<u32, Flow> {
+    match u32::try_from(amount) {
+        Ok(amount) if amount < i64::BITS => Ok(amount),
+        _ => Err(Flow::error(format!("Shift amount {} out of range", amount))),
+    }
+}
+
+thread_local! {
+    static DISPLAYING: RefCell<Vec<*const ()>> = const { RefCell::new(Vec::new()) };
+    static COMPARING: RefCell<Vec<(*const (), *const ())>> = const { RefCell::new(Vec::new()) };
+}
+
+// Prints `cycle` instead of recursing into a container that is already being printed.
+fn display_once(
+    container: *const (),
+    f: &mut std::fmt::Formatter<'_>,
+    cycle: &str,
+    display: impl FnOnce(&mut std::fmt::Formatter<'_>) -> std::fmt::Result,
+) -> std::fmt::Result {
+    if DISPLAYING.with(|displaying| displaying.borrow().contains(&container)) {
+        return write!(f, "{}", cycle);
+    }
+
+    DISPLAYING.with(|displaying| displaying.borrow_mut().push(container));
+    let result = display(f);
+    DISPLAYING.with(|displaying| displaying.borrow_mut().pop());
+    result
+}
+
+// Treats a pair of containers that is already being compared as equal, so cycles terminate:
+// if nothing else differs, two structures that loop back the same way are equal.
+fn compare_once(left: *const (), right: *const (), compare: impl FnOnce() -> bool) -> bool {
+    let pair = (left, right);
+    if COMPARING.with(|comparing| comparing.borrow().contains(&pair)) {
+        return true;
+    }
+
+    COMPARING.with(|comparing| comparing.borrow_mut().push(pair));
+    let result = compare();
+    COMPARING.with(|comparing| comparing.borrow_mut().pop());
+    result
+}
+
+impl PartialEq for Value {
+    fn eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (Value::Number(a), Value::Number(b)) => a == b,
+            (Value::Integer(a), Value::Integer(b)) => a == b,
+            (Value::Integer(a), Value::Number(b)) | (Value::Number(b), Value::Integer(a)) => {
+                *a as f64 == *b
+            }
+            (Value::Bool(a), Value::Bool(b)) => a == b,
+            (Value::String(a), Value::String(b)) => a == b,
+            (Value::Object(a), Value::Object(b)) => {
+                Rc::ptr_eq(a, b)
+                    || compare_once(
+                        Rc::as_ptr(a) as *const (),
+                        Rc::as_ptr(b) as *const (),
+                        || a == b,
+                    )
+            }
+            (Value::Method(a), Value::Method(b)) => Rc::ptr_eq(a, b) || a == b,
+            (Value::NativeMethod(a), Value::NativeMethod(b)) => Rc::ptr_eq(a, b) || a == b,
+            (Value::NativeFunction(a), Value::NativeFunction(b)) => Rc::ptr_eq(a, b) || a == b,
+            (Value::Array(a), Value::Array(b)) => {
+                Rc::ptr_eq(a, b)
+                    || compare_once(
+                        Rc::as_ptr(a) as *const (),
+                        Rc::as_ptr(b) as *const (),
+                        || a.borrow().elements == b.borrow().elements,
+                    )
+            }
+            (Value::NativeObject(a), Value::NativeObject(b)) => Rc::ptr_eq(a, b) || a == b,
+            (Value::Null, Value::Null) => true,
+            (Value::Void, Value::Void) => true,
+            _ => false,
         }
     }
 }
@@ -266,10 +464,12 @@ impl Value {
 impl Display for Value {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
-            Value::Number(n) => write!(f, "{}", n),
-            Value::String(s) => write!(f, "{}", s.borrow()),
+            // Floats always show a fractional part so they can be told apart from integers.
+            Value::Number(n) => write!(f, "{:?}", n),
+            Value::Integer(n) => write!(f, "{}", n),
+            Value::String(s) => write!(f, "{}", s),
             Value::Bool(b) => write!(f, "{}", b),
-            Value::Array(arr) => {
+            Value::Array(arr) => display_once(Rc::as_ptr(arr) as *const (), f, "[...]", |f| {
                 write!(f, "[")?;
                 for (i, elem) in arr.borrow().elements.iter().enumerate() {
                     if i > 0 {
@@ -278,8 +478,8 @@ impl Display for Value {
                     write!(f, "{}", elem)?;
                 }
                 write!(f, "]")
-            }
-            Value::Object(obj) => {
+            }),
+            Value::Object(obj) => display_once(Rc::as_ptr(obj) as *const (), f, "{...}", |f| {
                 write!(f, "{{ ")?;
                 let mut first = true;
                 for (key, val) in obj.borrow().values.iter() {
@@ -290,7 +490,7 @@ impl Display for Value {
                     write!(f, "{} = {}", key, val)?;
                 }
                 write!(f, " }}")
-            }
+            }),
             Value::Method(method) => {
                 write!(f, "<method {}>", method.borrow().declaration.signature.name)
             }
@@ -300,6 +500,13 @@ impl Display for Value {
             Value::NativeFunction(method) => {
                 write!(f, "<native method {:p}>", method.borrow().function)
             }
+            Value::NativeObject(object) => match object.try_borrow() {
+                Ok(object) => match &object.native_type.display {
+                    Some(display) => write!(f, "{}", display(object.data.as_ref())),
+                    None => write!(f, "<{}>", object.type_name()),
+                },
+                Err(_) => write!(f, "<native object>"),
+            },
             Value::Null => write!(f, "null"),
             Value::Void => write!(f, "void"),
         }
@@ -1,10 +1,13 @@
-use crate::token::{Token, TokenType};
+use crate::token::{Comment, Token, TokenType};
 
 pub struct Lexer {
     input: Vec<char>,
     position: usize,
     line: usize,
     column: usize,
+    start_line: usize,
+    start_column: usize,
+    comments: Vec<Comment>,
 }
 
 impl Lexer {
@@ -14,9 +17,17 @@ impl Lexer {
             position: 0,
             line: 1,
             column: 1,
+            start_line: 1,
+            start_column: 1,
+            comments: Vec::new(),
         }
     }
 
+    /// The comments skipped so far, in source order.
+    pub fn comments(&self) -> &[Comment] {
+        &self.comments
+    }
+
     pub fn lex(&mut self) -> Vec<Token> {
         let mut tokens = Vec::new();
         loop {
@@ -31,6 +42,8 @@ impl Lexer {
     }
     fn read_token(&mut self) -> Token {
         self.skip_whitespace();
+        self.start_line = self.line;
+        self.start_column = self.column;
 
         if let Some(c) = self.advance() {
             match c {
@@ -49,6 +62,8 @@ impl Lexer {
                 '-' => self.token(TokenType::Minus, "-"),
                 '*' => self.token(TokenType::Star, "*"),
                 '%' => self.token(TokenType::Percent, "%"),
+                '^' => self.token(TokenType::Caret, "^"),
+                '~' => self.token(TokenType::Tilde, "~"),
 
                 // Two-character tokens
                 '=' => {
@@ -68,6 +83,8 @@ impl Lexer {
                 '>' => {
                     if self.match_advance('=') {
                         self.token(TokenType::GreaterEqual, ">=")
+                    } else if self.match_advance('>') {
+                        self.token(TokenType::GreaterGreater, ">>")
                     } else {
                         self.token(TokenType::Greater, ">")
                     }
@@ -75,6 +92,8 @@ impl Lexer {
                 '<' => {
                     if self.match_advance('=') {
                         self.token(TokenType::LessEqual, "<=")
+                    } else if self.match_advance('<') {
+                        self.token(TokenType::LessLess, "<<")
                     } else {
                         self.token(TokenType::Less, "<")
                     }
@@ -83,24 +102,18 @@ impl Lexer {
                     if self.match_advance('&') {
                         self.token(TokenType::And, "&&")
                     } else {
-                        self.token(TokenType::Invalid, "&")
+                        self.token(TokenType::Ampersand, "&")
                     }
                 }
                 '|' => {
                     if self.match_advance('|') {
                         self.token(TokenType::Or, "||")
                     } else {
-                        self.token(TokenType::Invalid, "|")
+                        self.token(TokenType::Pipe, "|")
                     }
                 }
                 '"' => self.read_string(),
-                '/' => {
-                    if self.match_advance('/') {
-                        self.read_comments()
-                    } else {
-                        self.token(TokenType::Slash, "/")
-                    }
-                }
+                '/' => self.token(TokenType::Slash, "/"),
                 c if c.is_digit(10) => self.read_number(c),
                 c if c.is_alphabetic() || c == '_' => self.read_identifier(c),
                 _ => self.token(TokenType::Invalid, &c.to_string()),
@@ -110,44 +123,61 @@ impl Lexer {
         }
     }
 
+    // Letters and underscores are read as part of the literal, so a malformed one such
+    // as `0xZ` or `1_` stays a single token; the parser validates and converts it.
     fn read_number(&mut self, c: char) -> Token {
         let mut lexeme = String::new();
         lexeme.push(c);
+        let decimal = !(c == '0' && matches!(self.peek(), Some('x' | 'X' | 'b' | 'B' | 'o' | 'O')));
 
         // integer part
+        self.read_digits(&mut lexeme, decimal);
+
+        // decimal part
+        if decimal
+            && self.peek() == Some('.')
+            && self.peek_next().is_some_and(|c| c.is_ascii_digit())
+        {
+            self.advance();
+            lexeme.push('.');
+            self.read_digits(&mut lexeme, decimal);
+        }
+
+        self.token(TokenType::NumberLiteral, &lexeme)
+    }
+
+    fn read_digits(&mut self, lexeme: &mut String, decimal: bool) {
         while let Some(current_char) = self.peek() {
-            if current_char.is_digit(10) {
-                lexeme.push(current_char);
-                self.advance();
-            } else {
+            if !(current_char.is_alphanumeric() || current_char == '_') {
                 break;
             }
-        }
+            lexeme.push(current_char);
+            self.advance();
 
-        // decimal part
-        if self.match_advance('.') {
-            lexeme.push('.');
-            while let Some(current_char) = self.peek() {
-                if current_char.is_digit(10) {
-                    lexeme.push(current_char);
+            // exponent sign
+            if decimal && matches!(current_char, 'e' | 'E') {
+                if let Some(sign @ ('+' | '-')) = self.peek() {
+                    lexeme.push(sign);
                     self.advance();
-                } else {
-                    break;
                 }
             }
         }
-
-        self.token(TokenType::NumberLiteral, &lexeme)
     }
 
-    fn read_comments(&mut self) -> Token {
+    fn read_comments(&mut self) {
+        let line = self.line;
+        let mut text = String::new();
         while let Some(current_char) = self.peek() {
             if current_char == '\n' {
                 break;
             }
+            text.push(current_char);
             self.advance();
         }
-        self.read_token()
+        self.comments.push(Comment {
+            text: text.trim_end().to_string(),
+            line,
+        });
     }
 
     fn read_string(&mut self) -> Token {
@@ -156,14 +186,14 @@ impl Lexer {
         while let Some(current_char) = self.peek() {
             if current_char == '"' {
                 self.advance();
-                break;
+                lexeme.push('"');
+                return self.token(TokenType::StringLiteral, &lexeme);
             } else {
                 lexeme.push(current_char);
                 self.advance();
             }
         }
-        lexeme.push('"');
-        self.token(TokenType::StringLiteral, &lexeme)
+        self.token(TokenType::Invalid, &lexeme)
     }
 
     fn read_identifier(&mut self, c: char) -> Token {
@@ -194,6 +224,10 @@ impl Lexer {
             "break" => TokenType::Break,
             "continue" => TokenType::Continue,
             "return" => TokenType::Return,
+            "try" => TokenType::Try,
+            "catch" => TokenType::Catch,
+            "finally" => TokenType::Finally,
+            "throw" => TokenType::Throw,
             _ => TokenType::Identifier,
         };
 
@@ -204,8 +238,8 @@ impl Lexer {
         Token {
             token_type,
             lexeme: lexeme.to_string(),
-            line: self.line,
-            column: self.column - lexeme.len(),
+            line: self.start_line,
+            column: self.start_column,
         }
     }
 
@@ -234,6 +268,10 @@ impl Lexer {
         Some(self.input[self.position])
     }
 
+    fn peek_next(&self) -> Option<char> {
+        self.input.get(self.position + 1).copied()
+    }
+
     fn match_advance(&mut self, expected: char) -> bool {
         if self.peek() == Some(expected) {
             self.advance();
@@ -247,6 +285,8 @@ impl Lexer {
         while let Some(current_char) = self.peek() {
             if current_char.is_whitespace() {
                 self.advance();
+            } else if current_char == '/' && self.peek_next() == Some('/') {
+                self.read_comments();
             } else {
                 break;
             }
@@ -292,6 +332,21 @@ mod tests {
         assert_eq!(lexer.read_token().token_type, TokenType::Bang);
     }
 
+    #[test]
+    fn test_bitwise_operators() {
+        let input = "& | ^ ~ << >> < <";
+        let mut lexer = Lexer::new(input);
+
+        assert_eq!(lexer.read_token().token_type, TokenType::Ampersand);
+        assert_eq!(lexer.read_token().token_type, TokenType::Pipe);
+        assert_eq!(lexer.read_token().token_type, TokenType::Caret);
+        assert_eq!(lexer.read_token().token_type, TokenType::Tilde);
+        assert_eq!(lexer.read_token().token_type, TokenType::LessLess);
+        assert_eq!(lexer.read_token().token_type, TokenType::GreaterGreater);
+        assert_eq!(lexer.read_token().token_type, TokenType::Less);
+        assert_eq!(lexer.read_token().token_type, TokenType::Less);
+    }
+
     #[test]
     fn test_string_literal() {
         let input = "\"Hello, World!\"";
@@ -313,6 +368,30 @@ mod tests {
         let token2 = lexer.read_token();
         assert_eq!(token2.token_type, TokenType::NumberLiteral);
         assert_eq!(token2.lexeme, "45.67");
+
+        let input = "0xFF_FF 0b1010 1_000.5e-3 2E+8 0x1e-2 1.length 0xZ";
+        let lexemes: Vec<_> = Lexer::new(input)
+            .lex()
+            .into_iter()
+            .map(|token| token.lexeme)
+            .collect();
+        assert_eq!(
+            lexemes,
+            [
+                "0xFF_FF",
+                "0b1010",
+                "1_000.5e-3",
+                "2E+8",
+                "0x1e",
+                "-",
+                "2",
+                "1",
+                ".",
+                "length",
+                "0xZ",
+                ""
+            ]
+        );
     }
 
     #[test]
@@ -320,6 +399,22 @@ mod tests {
         let input = "// this is a comment\nvar";
         let mut lexer = Lexer::new(input);
         assert_eq!(lexer.read_token().token_type, TokenType::Var);
+
+        let mut lexer = Lexer::new("var x = 1; // one  \n\n// two\nx;");
+        lexer.lex();
+        assert_eq!(
+            lexer.comments(),
+            [
+                Comment {
+                    text: "// one".to_string(),
+                    line: 1
+                },
+                Comment {
+                    text: "// two".to_string(),
+                    line: 3
+                },
+            ]
+        );
     }
 
     #[test]
@@ -371,6 +466,24 @@ mod tests {
         assert_eq!(token3.lexeme, "baz123");
     }
 
+    #[test]
+    fn test_multiline_and_unicode_positions() {
+        let input = "\"héllo\nwörld\" x\n\"unterminated";
+        let mut lexer = Lexer::new(input);
+
+        let token1 = lexer.read_token();
+        assert_eq!(token1.token_type, TokenType::StringLiteral);
+        assert_eq!((token1.line, token1.column), (1, 1));
+
+        let token2 = lexer.read_token();
+        assert_eq!(token2.token_type, TokenType::Identifier);
+        assert_eq!((token2.line, token2.column), (2, 8));
+
+        let token3 = lexer.read_token();
+        assert_eq!(token3.token_type, TokenType::Invalid);
+        assert_eq!((token3.line, token3.column), (3, 1));
+    }
+
     #[test]
     fn test_complex_expression() {
         let input = "if (x >= 10 && y <= 20) { return true; }";
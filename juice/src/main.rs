@@ -1,38 +1,239 @@
-use juice::{flow::Flow, interpreter::Interpreter};
+use checker::Checker;
+use juice::{error::Error, interpreter::Interpreter};
 use lexer::Lexer;
 use parser::Parser;
+use std::{
+    io::{self, Read},
+    process,
+};
 
 pub mod ast;
+pub mod checker;
+pub mod formatter;
 pub mod juice;
 pub mod lexer;
 pub mod parser;
 pub mod token;
 
-fn eval(source: &str) {
-    let tokens = Lexer::new(source).lex();
-    let statements = Parser::new(tokens).parse();
-    let result = Interpreter::new()
-        .with_std()
-        .unwrap()
-        .interprete(&statements);
+const USAGE: &str =
+    "Usage: juice [run|check|fmt|tokens|ast] [options] <file | -e code | -> [-- args...]
+
+Commands:
+  run       Run a script (the default)
+  check     Parse and check a script without running it
+  fmt       Print a script in canonical format
+  tokens    Print the tokens of a script with their positions
+  ast       Print the syntax tree of a script
+
+Options:
+  -e <code>          Use <code> as the script
+  -                  Read the script from stdin
+  --seed <n>         Seed the default random generator
+  --allow-fs <dir>   Let the script access files under <dir>
+  --no-env           Hide environment variables from the script
+  --check            With fmt, only report whether the script is formatted";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Command {
+    Run,
+    Check,
+    Fmt,
+    Tokens,
+    Ast,
+}
+
+enum Source {
+    File(String),
+    Inline(String),
+    Stdin,
+}
+
+struct Options {
+    command: Command,
+    source: Option<Source>,
+    seed: Option<u64>,
+    fs_root: Option<String>,
+    env: bool,
+    check: bool,
+    args: Vec<String>,
+}
+
+impl Options {
+    fn parse(args: impl Iterator<Item = String>) -> Result<Self, String> {
+        let mut options = Options {
+            command: Command::Run,
+            source: None,
+            seed: None,
+            fs_root: None,
+            env: true,
+            check: false,
+            args: Vec::new(),
+        };
+        let mut args = args.peekable();
+        let command = match args.peek().map(String::as_str) {
+            Some("run") => Some(Command::Run),
+            Some("check") => Some(Command::Check),
+            Some("fmt") => Some(Command::Fmt),
+            Some("tokens") => Some(Command::Tokens),
+            Some("ast") => Some(Command::Ast),
+            _ => None,
+        };
+        if let Some(command) = command {
+            options.command = command;
+            args.next();
+        }
+
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "--" => options.args.extend(args.by_ref()),
+                "-e" => match args.next() {
+                    Some(code) => options.set_source(Source::Inline(code))?,
+                    None => return Err("-e expects code to run".to_string()),
+                },
+                "-" => options.set_source(Source::Stdin)?,
+                "--no-env" => options.env = false,
+                "--check" => options.check = true,
+                "--seed" => match args.next().map(|seed| seed.parse::<u64>()) {
+                    Some(Ok(seed)) => options.seed = Some(seed),
+                    _ => return Err("--seed expects a non-negative integer".to_string()),
+                },
+                "--allow-fs" => match args.next() {
+                    Some(root) => options.fs_root = Some(root),
+                    None => return Err("--allow-fs expects a directory".to_string()),
+                },
+                "-h" | "--help" => {
+                    println!("{}", USAGE);
+                    process::exit(0);
+                }
+                _ if arg.starts_with('-') => return Err(format!("Unknown option {}", arg)),
+                _ => options.set_source(Source::File(arg))?,
+            }
+        }
+        if options.check && options.command != Command::Fmt {
+            return Err("--check only applies to fmt".to_string());
+        }
+        Ok(options)
+    }
 
-    if let Err(flow) = &result {
-        if let Flow::Error(msg) = flow {
-            panic!("{}", msg);
+    fn source_name(&self) -> &str {
+        match &self.source {
+            Some(Source::File(filename)) => filename,
+            Some(Source::Inline(_)) => "-e",
+            Some(Source::Stdin) | None => "stdin",
         }
     }
+
+    fn set_source(&mut self, source: Source) -> Result<(), String> {
+        if self.source.is_some() {
+            return Err("Expected a single script".to_string());
+        }
+        self.source = Some(source);
+        Ok(())
+    }
+}
+
+fn read_source(source: &Source) -> Result<String, String> {
+    match source {
+        Source::File(filename) => std::fs::read_to_string(filename)
+            .map_err(|err| format!("Error reading {}: {}", filename, err)),
+        Source::Inline(code) => Ok(code.clone()),
+        Source::Stdin => {
+            let mut source = String::new();
+            io::stdin()
+                .read_to_string(&mut source)
+                .map_err(|err| format!("Error reading stdin: {}", err))?;
+            Ok(source)
+        }
+    }
+}
+
+fn eval(source: &str, options: &Options) -> Result<(), Error> {
+    let tokens = Lexer::new(source).lex();
+    let statements = Parser::new(tokens).parse()?;
+    let mut interpreter = Interpreter::new();
+    interpreter.with_std()?.with_args(&options.args)?;
+    if options.env {
+        interpreter.with_env()?;
+    }
+    if let Some(seed) = options.seed {
+        interpreter.with_seed(seed)?;
+    }
+    if let Some(root) = &options.fs_root {
+        interpreter.with_fs(root)?;
+    }
+    interpreter.interprete(&statements)?;
+    Ok(())
+}
+
+fn check(source: &str) -> Result<(), Vec<Error>> {
+    let tokens = Lexer::new(source).lex();
+    let statements = Parser::new(tokens).parse().map_err(|err| vec![err])?;
+    let errors = Checker::new().check(&statements);
+    if errors.is_empty() {
+        Ok(())
+    } else {
+        Err(errors)
+    }
 }
 
 fn main() {
-    if let Some(filename) = std::env::args().nth(1) {
-        if !filename.ends_with(".juice") {
-            panic!("File must have .juice extension");
+    let options = match Options::parse(std::env::args().skip(1)) {
+        Ok(options) => options,
+        Err(message) => {
+            eprintln!("{}\n\n{}", message, USAGE);
+            process::exit(1);
         }
-        match std::fs::read_to_string(filename) {
-            Ok(source) => eval(&source),
-            Err(err) => panic!("Error reading file: {}", err),
+    };
+    let source = match &options.source {
+        Some(source) => read_source(source),
+        None => {
+            eprintln!("{}", USAGE);
+            process::exit(1);
         }
-    } else {
-        println!("Please provide a filename as argument");
+    };
+    let source = source.unwrap_or_else(|message| {
+        eprintln!("{}", message);
+        process::exit(1);
+    });
+
+    let result = match options.command {
+        Command::Run => eval(&source, &options),
+        Command::Check => {
+            if let Err(errors) = check(&source) {
+                for error in errors {
+                    eprintln!("{}", error);
+                }
+                process::exit(1);
+            }
+            Ok(())
+        }
+        Command::Fmt => formatter::format_source(&source).map(|formatted| {
+            if !options.check {
+                print!("{}", formatted);
+            } else if formatted != source {
+                eprintln!("{} is not formatted", options.source_name());
+                process::exit(1);
+            }
+        }),
+        Command::Tokens => {
+            for token in Lexer::new(&source).lex() {
+                println!(
+                    "{}:{}\t{:?}\t{:?}",
+                    token.line, token.column, token.token_type, token.lexeme
+                );
+            }
+            Ok(())
+        }
+        Command::Ast => Parser::new(Lexer::new(&source).lex())
+            .parse()
+            .map(|statements| println!("{:#?}", statements)),
+    };
+
+    if let Err(err) = result {
+        if let Some(code) = err.exit_code() {
+            process::exit(code);
+        }
+        eprintln!("{}", err);
+        process::exit(1);
     }
 }
@@ -1,142 +1,191 @@
-use std::collections::HashMap;
-use std::env::args;
-
 use crate::ast::{
-    BinaryOp, Expression, MethodDeclaration, MethodSignature, Parameter, Statement, UnaryOp,
+    BinaryOp, Block, Expression, MethodDeclaration, MethodSignature, Parameter, Span, Spanned,
+    Statement, StatementKind, UnaryOp,
 };
+use crate::juice::error::{Error, ErrorKind};
+use crate::juice::symbol::{intern, Symbol};
 use crate::token::{Token, TokenType};
 
+const MAX_NESTING_DEPTH: usize = 128;
+
+// Binary operators from loosest to tightest binding. As in C, the bitwise operators bind
+// looser than equality.
+const BINARY_OPERATORS: &[&[(TokenType, BinaryOp)]] = &[
+    &[(TokenType::Or, BinaryOp::Or)],
+    &[(TokenType::And, BinaryOp::And)],
+    &[(TokenType::Pipe, BinaryOp::BitOr)],
+    &[(TokenType::Caret, BinaryOp::BitXor)],
+    &[(TokenType::Ampersand, BinaryOp::BitAnd)],
+    &[
+        (TokenType::EqualEqual, BinaryOp::Equal),
+        (TokenType::BangEqual, BinaryOp::NotEqual),
+    ],
+    &[
+        (TokenType::Less, BinaryOp::Less),
+        (TokenType::LessEqual, BinaryOp::LessEqual),
+        (TokenType::Greater, BinaryOp::Greater),
+        (TokenType::GreaterEqual, BinaryOp::GreaterEqual),
+    ],
+    &[
+        (TokenType::LessLess, BinaryOp::ShiftLeft),
+        (TokenType::GreaterGreater, BinaryOp::ShiftRight),
+    ],
+    &[
+        (TokenType::Plus, BinaryOp::Add),
+        (TokenType::Minus, BinaryOp::Subtract),
+    ],
+    &[
+        (TokenType::Star, BinaryOp::Multiply),
+        (TokenType::Slash, BinaryOp::Divide),
+        (TokenType::Percent, BinaryOp::Modulo),
+    ],
+];
+
 pub struct Parser {
     tokens: Vec<Token>,
     current: usize,
+    depth: usize,
 }
 
 impl Parser {
     pub fn new(tokens: Vec<Token>) -> Self {
-        Parser { tokens, current: 0 }
+        Parser {
+            tokens,
+            current: 0,
+            depth: 0,
+        }
     }
 
-    pub fn parse(&mut self) -> Vec<Statement> {
+    pub fn parse(&mut self) -> Result<Vec<Statement>, Error> {
         let mut statements = Vec::new();
         while !self.is_at_end() {
-            if let Some(stmt) = self.statement() {
+            if let Some(stmt) = self.statement()? {
                 statements.push(stmt);
             }
         }
-        statements
+        Ok(statements)
     }
 
-    fn statement(&mut self) -> Option<Statement> {
-        match self.peek().token_type {
-            TokenType::Object => Some(self.object_declaration()),
-            TokenType::Trait => Some(self.trait_declaration()),
-            TokenType::Var => Some(self.var_declaration()),
-            TokenType::While => Some(self.while_statement()),
-            TokenType::For => Some(self.for_statement()),
-            TokenType::If => Some(self.if_statement()),
-            TokenType::Break => Some(self.break_statement()),
-            TokenType::Continue => Some(self.continue_statement()),
-            TokenType::Return => Some(self.return_statement()),
-            _ => Some(self.expression_statement()),
-        }
+    fn statement(&mut self) -> Result<Option<Statement>, Error> {
+        let start = self.line();
+        let statement = match self.peek().token_type {
+            TokenType::Object => self.object_declaration()?,
+            TokenType::Trait => self.trait_declaration()?,
+            TokenType::Var => self.var_declaration()?,
+            TokenType::While => self.while_statement()?,
+            TokenType::For => self.for_statement()?,
+            TokenType::If => self.if_statement()?,
+            TokenType::Break => self.break_statement()?,
+            TokenType::Continue => self.continue_statement()?,
+            TokenType::Return => self.return_statement()?,
+            TokenType::Throw => self.throw_statement()?,
+            TokenType::Try => self.try_statement()?,
+            _ => self.expression_statement()?,
+        };
+        Ok(Some(Statement {
+            node: statement,
+            span: self.span_from(start),
+        }))
     }
 
-    fn object_declaration(&mut self) -> Statement {
-        self.consume(TokenType::Object, "Expected 'object' keyword");
-        let name = self.consume_identifier("Expected object name");
-        let type_annotation = self.type_annotation();
+    fn object_declaration(&mut self) -> Result<StatementKind, Error> {
+        self.consume(TokenType::Object, "Expected 'object' keyword")?;
+        let name = self.consume_identifier("Expected object name")?;
+        let type_annotation = self.type_annotation()?;
 
-        self.consume(TokenType::LeftBrace, "Expected '{' after object name");
+        self.consume(TokenType::LeftBrace, "Expected '{' after object name")?;
 
         let mut methods = Vec::new();
         while !self.check(TokenType::RightBrace) && !self.is_at_end() {
-            methods.push(self.method_declaration());
+            methods.push(self.method_declaration()?);
         }
 
-        self.consume(TokenType::RightBrace, "Expected '}' after object body");
+        self.consume(TokenType::RightBrace, "Expected '}' after object body")?;
 
-        Statement::Object {
+        Ok(StatementKind::Object {
             name,
             type_annotation,
             methods,
-        }
+        })
     }
 
-    fn trait_declaration(&mut self) -> Statement {
-        self.consume(TokenType::Trait, "Expected 'trait' keyword");
-        let name = self.consume_identifier("Expected trait name");
-        let type_annotation = self.type_annotation();
+    fn trait_declaration(&mut self) -> Result<StatementKind, Error> {
+        self.consume(TokenType::Trait, "Expected 'trait' keyword")?;
+        let name = self.consume_identifier("Expected trait name")?;
+        let type_annotation = self.type_annotation()?;
 
-        self.consume(TokenType::LeftBrace, "Expected '{' after trait name");
+        self.consume(TokenType::LeftBrace, "Expected '{' after trait name")?;
 
         let mut method_signatures = Vec::new();
         while !self.check(TokenType::RightBrace) && !self.is_at_end() {
-            method_signatures.push(self.method_signature());
-            self.consume(TokenType::Semicolon, "Expected ';' after method signature");
+            method_signatures.push(self.method_signature()?);
+            self.consume(TokenType::Semicolon, "Expected ';' after method signature")?;
         }
 
-        self.consume(TokenType::RightBrace, "Expected '}' after trait body");
+        self.consume(TokenType::RightBrace, "Expected '}' after trait body")?;
 
-        Statement::Trait {
+        Ok(StatementKind::Trait {
             name,
             type_annotation,
             method_signatures,
-        }
+        })
     }
 
-    fn var_declaration(&mut self) -> Statement {
-        self.consume(TokenType::Var, "Expected 'var' keyword");
-        let name = self.consume_identifier("Expected variable name");
-        let type_annotation = self.type_annotation();
+    fn var_declaration(&mut self) -> Result<StatementKind, Error> {
+        self.consume(TokenType::Var, "Expected 'var' keyword")?;
+        let name = self.consume_symbol("Expected variable name")?;
+        let type_annotation = self.type_annotation()?;
 
-        self.consume(TokenType::Equal, "Expected '=' after variable name");
-        let initializer = Box::new(self.expression());
+        self.consume(TokenType::Equal, "Expected '=' after variable name")?;
+        let initializer = Box::new(self.expression()?);
 
         self.consume(
             TokenType::Semicolon,
             "Expected ';' after variable declaration",
-        );
+        )?;
 
-        Statement::Var {
+        Ok(StatementKind::Var {
             name,
             type_annotation,
             initializer,
-        }
+        })
     }
 
-    fn method_declaration(&mut self) -> MethodDeclaration {
-        let signature = self.method_signature();
-        let body = self.block();
+    fn method_declaration(&mut self) -> Result<MethodDeclaration, Error> {
+        let signature = self.method_signature()?;
+        let body = self.block()?;
 
-        MethodDeclaration { signature, body }
+        Ok(MethodDeclaration { signature, body })
     }
 
-    fn method_signature(&mut self) -> MethodSignature {
-        let name = self.consume_identifier("Expected method name");
+    fn method_signature(&mut self) -> Result<MethodSignature, Error> {
+        let start = self.line();
+        let name = self.consume_symbol("Expected method name")?;
 
-        self.consume(TokenType::LeftParen, "Expected '(' after method name");
+        self.consume(TokenType::LeftParen, "Expected '(' after method name")?;
         let params = if !self.check(TokenType::RightParen) {
-            self.parameter_list()
+            self.parameter_list()?
         } else {
             Vec::new()
         };
-        self.consume(TokenType::RightParen, "Expected ')' after parameters");
+        self.consume(TokenType::RightParen, "Expected ')' after parameters")?;
 
-        let return_type = self.type_annotation();
+        let return_type = self.type_annotation()?;
 
-        MethodSignature {
+        Ok(MethodSignature {
             name,
             params,
             return_type,
-        }
+            span: self.span_from(start),
+        })
     }
 
-    fn parameter_list(&mut self) -> Vec<Parameter> {
+    fn parameter_list(&mut self) -> Result<Vec<Parameter>, Error> {
         let mut params = Vec::new();
 
         loop {
-            let name = self.consume_identifier("Expected parameter name");
-            let type_annotation = self.type_annotation();
+            let name = self.consume_symbol("Expected parameter name")?;
+            let type_annotation = self.type_annotation()?;
 
             params.push(Parameter {
                 name,
@@ -148,292 +197,246 @@ impl Parser {
             }
         }
 
-        params
+        Ok(params)
     }
 
-    fn type_annotation(&mut self) -> Option<Vec<String>> {
+    fn type_annotation(&mut self) -> Result<Option<Vec<String>>, Error> {
         if !self.match_token(TokenType::Colon) {
-            return None;
+            return Ok(None);
         }
 
         let mut types = Vec::new();
         loop {
-            types.push(self.type_identifier());
+            types.push(self.type_identifier()?);
             if !self.match_token(TokenType::Plus) {
                 break;
             }
         }
-        Some(types)
+        Ok(Some(types))
     }
 
-    fn type_identifier(&mut self) -> String {
+    fn type_identifier(&mut self) -> Result<String, Error> {
         let mut parts = String::new();
-        parts.push_str(self.consume_identifier("Expected type name").as_str());
+        parts.push_str(self.consume_identifier("Expected type name")?.as_str());
 
         while self.match_token(TokenType::Dot) {
             parts.push_str(
-                self.consume_identifier("Expected identifier after '.'")
+                self.consume_identifier("Expected identifier after '.'")?
                     .as_str(),
             );
         }
 
-        parts
+        Ok(parts)
     }
 
-    fn while_statement(&mut self) -> Statement {
-        self.consume(TokenType::While, "Expected 'while' keyword");
-        self.consume(TokenType::LeftParen, "Expected '(' after 'while'");
-        let condition = Box::new(self.expression());
-        self.consume(TokenType::RightParen, "Expected ')' after condition");
-        let body = self.block();
+    fn while_statement(&mut self) -> Result<StatementKind, Error> {
+        self.consume(TokenType::While, "Expected 'while' keyword")?;
+        self.consume(TokenType::LeftParen, "Expected '(' after 'while'")?;
+        let condition = Box::new(self.expression()?);
+        self.consume(TokenType::RightParen, "Expected ')' after condition")?;
+        let body = self.block()?;
 
-        Statement::While { condition, body }
+        Ok(StatementKind::While { condition, body })
     }
 
-    fn for_statement(&mut self) -> Statement {
-        self.consume(TokenType::For, "Expected 'for' keyword");
-        self.consume(TokenType::LeftParen, "Expected '(' after 'for'");
-        self.consume(TokenType::Var, "Expected 'var' keyword in for loop");
-        let variable = self.consume_identifier("Expected iteration variable name");
-        self.consume(TokenType::In, "Expected 'in' keyword");
-        let iterator = Box::new(self.expression());
+    fn for_statement(&mut self) -> Result<StatementKind, Error> {
+        self.consume(TokenType::For, "Expected 'for' keyword")?;
+        self.consume(TokenType::LeftParen, "Expected '(' after 'for'")?;
+        self.consume(TokenType::Var, "Expected 'var' keyword in for loop")?;
+        let variable = self.consume_symbol("Expected iteration variable name")?;
+        self.consume(TokenType::In, "Expected 'in' keyword")?;
+        let iterator = Box::new(self.expression()?);
         self.consume(
             TokenType::RightParen,
             "Expected ')' after iteration variable",
-        );
-        let body = self.block();
+        )?;
+        let body = self.block()?;
 
-        Statement::For {
+        Ok(StatementKind::For {
             variable,
             iterator,
             body,
-        }
+        })
     }
 
-    fn if_statement(&mut self) -> Statement {
-        self.consume(TokenType::If, "Expected 'if' keyword");
-        self.consume(TokenType::LeftParen, "Expected '(' after 'if'");
-        let condition = Box::new(self.expression());
-        self.consume(TokenType::RightParen, "Expected ')' after condition");
-        let then_branch = self.block();
+    fn if_statement(&mut self) -> Result<StatementKind, Error> {
+        self.consume(TokenType::If, "Expected 'if' keyword")?;
+        self.consume(TokenType::LeftParen, "Expected '(' after 'if'")?;
+        let condition = Box::new(self.expression()?);
+        self.consume(TokenType::RightParen, "Expected ')' after condition")?;
+        let then_branch = self.block()?;
 
         let else_branch = if self.match_token(TokenType::Else) {
-            Some(self.block())
+            Some(self.block()?)
         } else {
             None
         };
 
-        Statement::If {
+        Ok(StatementKind::If {
             condition,
             then_branch,
             else_branch,
-        }
+        })
     }
 
-    fn break_statement(&mut self) -> Statement {
-        self.consume(TokenType::Break, "Expected 'break' keyword");
-        self.consume(TokenType::Semicolon, "Expected ';' after break statement");
-        Statement::Break
+    fn break_statement(&mut self) -> Result<StatementKind, Error> {
+        self.consume(TokenType::Break, "Expected 'break' keyword")?;
+        self.consume(TokenType::Semicolon, "Expected ';' after break statement")?;
+        Ok(StatementKind::Break)
     }
 
-    fn continue_statement(&mut self) -> Statement {
-        self.consume(TokenType::Continue, "Expected 'continue' keyword");
+    fn continue_statement(&mut self) -> Result<StatementKind, Error> {
+        self.consume(TokenType::Continue, "Expected 'continue' keyword")?;
         self.consume(
             TokenType::Semicolon,
             "Expected ';' after continue statement",
-        );
-        Statement::Continue
+        )?;
+        Ok(StatementKind::Continue)
     }
 
-    fn return_statement(&mut self) -> Statement {
-        self.consume(TokenType::Return, "Expected 'return' keyword");
+    fn return_statement(&mut self) -> Result<StatementKind, Error> {
+        self.consume(TokenType::Return, "Expected 'return' keyword")?;
         let value = if !self.check(TokenType::Semicolon) {
-            Some(self.expression())
+            Some(self.expression()?)
         } else {
             None
         };
-        self.consume(TokenType::Semicolon, "Expected ';' after return statement");
+        self.consume(TokenType::Semicolon, "Expected ';' after return statement")?;
 
-        Statement::Return(value)
+        Ok(StatementKind::Return(value))
     }
 
-    fn block(&mut self) -> Vec<Statement> {
-        self.consume(TokenType::LeftBrace, "Expected '{' before block");
-
-        let mut statements = Vec::new();
-        while !self.check(TokenType::RightBrace) {
-            if let Some(stmt) = self.statement() {
-                statements.push(stmt);
-            }
-        }
+    fn throw_statement(&mut self) -> Result<StatementKind, Error> {
+        self.consume(TokenType::Throw, "Expected 'throw' keyword")?;
+        let value = self.expression()?;
+        self.consume(TokenType::Semicolon, "Expected ';' after throw statement")?;
 
-        self.consume(TokenType::RightBrace, "Expected '}' after block");
-        statements
+        Ok(StatementKind::Throw(value))
     }
 
-    fn expression_statement(&mut self) -> Statement {
-        let expr = self.expression();
-        self.consume(TokenType::Semicolon, "Expected ';' after expression");
-        Statement::Expression(expr)
-    }
+    fn try_statement(&mut self) -> Result<StatementKind, Error> {
+        self.consume(TokenType::Try, "Expected 'try' keyword")?;
+        let body = self.block()?;
 
-    fn expression(&mut self) -> Expression {
-        self.assignment()
-    }
+        let catch = if self.match_token(TokenType::Catch) {
+            self.consume(TokenType::LeftParen, "Expected '(' after 'catch'")?;
+            let variable = self.consume_symbol("Expected error variable name")?;
+            self.consume(TokenType::RightParen, "Expected ')' after error variable")?;
+            Some((variable, self.block()?))
+        } else {
+            None
+        };
 
-    fn assignment(&mut self) -> Expression {
-        let expr = self.logical_or();
+        let finally = if self.match_token(TokenType::Finally) {
+            Some(self.block()?)
+        } else {
+            None
+        };
 
-        if self.match_token(TokenType::Equal) {
-            let value = Box::new(self.assignment());
-            return Expression::Assignment {
-                target: Box::new(expr),
-                value,
-            };
+        if catch.is_none() && finally.is_none() {
+            return Err(self.error("Expected 'catch' or 'finally' after try block"));
         }
 
-        expr
+        Ok(StatementKind::Try {
+            body,
+            catch,
+            finally,
+        })
     }
 
-    fn logical_or(&mut self) -> Expression {
-        let mut expr = self.logical_and();
+    fn block(&mut self) -> Result<Block, Error> {
+        let start = self.line();
+        self.consume(TokenType::LeftBrace, "Expected '{' before block")?;
+        self.enter()?;
 
-        while self.match_token(TokenType::Or) {
-            let right = self.logical_and();
-            expr = Expression::Binary {
-                left: Box::new(expr),
-                operator: BinaryOp::Or,
-                right: Box::new(right),
-            };
+        let mut statements = Vec::new();
+        while !self.check(TokenType::RightBrace) && !self.is_at_end() {
+            if let Some(stmt) = self.statement()? {
+                statements.push(stmt);
+            }
         }
 
-        expr
+        self.leave();
+        self.consume(TokenType::RightBrace, "Expected '}' after block")?;
+        Ok(Block {
+            node: statements,
+            span: self.span_from(start),
+        })
     }
 
-    fn logical_and(&mut self) -> Expression {
-        let mut expr = self.equality();
-
-        while self.match_token(TokenType::And) {
-            let right = self.equality();
-            expr = Expression::Binary {
-                left: Box::new(expr),
-                operator: BinaryOp::And,
-                right: Box::new(right),
-            };
-        }
-
-        expr
+    fn expression_statement(&mut self) -> Result<StatementKind, Error> {
+        let expr = self.expression()?;
+        self.consume(TokenType::Semicolon, "Expected ';' after expression")?;
+        Ok(StatementKind::Expression(expr))
     }
 
-    fn equality(&mut self) -> Expression {
-        let mut expr = self.comparison();
-
-        loop {
-            let op = if self.match_token(TokenType::EqualEqual) {
-                BinaryOp::Equal
-            } else if self.match_token(TokenType::BangEqual) {
-                BinaryOp::NotEqual
-            } else {
-                break;
-            };
-
-            let right = self.comparison();
-            expr = Expression::Binary {
-                left: Box::new(expr),
-                operator: op,
-                right: Box::new(right),
-            };
-        }
-
+    fn expression(&mut self) -> Result<Expression, Error> {
+        self.enter()?;
+        let expr = self.assignment();
+        self.leave();
         expr
     }
 
-    fn comparison(&mut self) -> Expression {
-        let mut expr = self.term();
-
-        loop {
-            let op = if self.match_token(TokenType::Less) {
-                BinaryOp::Less
-            } else if self.match_token(TokenType::LessEqual) {
-                BinaryOp::LessEqual
-            } else if self.match_token(TokenType::Greater) {
-                BinaryOp::Greater
-            } else if self.match_token(TokenType::GreaterEqual) {
-                BinaryOp::GreaterEqual
-            } else {
-                break;
-            };
+    fn assignment(&mut self) -> Result<Expression, Error> {
+        let expr = self.binary(0)?;
 
-            let right = self.term();
-            expr = Expression::Binary {
-                left: Box::new(expr),
-                operator: op,
-                right: Box::new(right),
-            };
+        if self.match_token(TokenType::Equal) {
+            let value = Box::new(self.assignment()?);
+            return Ok(Expression::Assignment {
+                target: Box::new(expr),
+                value,
+            });
         }
 
-        expr
+        Ok(expr)
     }
 
-    fn term(&mut self) -> Expression {
-        let mut expr = self.factor();
-
-        loop {
-            let op = if self.match_token(TokenType::Plus) {
-                BinaryOp::Add
-            } else if self.match_token(TokenType::Minus) {
-                BinaryOp::Subtract
-            } else {
-                break;
-            };
+    // Parses binary operators by precedence climbing. A single function for every level keeps
+    // the stack used per level of parentheses small, so deep nesting fails with an error instead
+    // of overflowing the stack.
+    fn binary(&mut self, min_level: usize) -> Result<Expression, Error> {
+        let mut expr = self.unary()?;
 
-            let right = self.factor();
+        while let Some((level, operator)) = self.binary_operator(min_level) {
+            // Operands bind tighter than the operator, which makes every level left-associative.
+            let right = self.binary(level + 1)?;
             expr = Expression::Binary {
                 left: Box::new(expr),
-                operator: op,
+                operator,
                 right: Box::new(right),
             };
         }
 
-        expr
+        Ok(expr)
     }
 
-    fn factor(&mut self) -> Expression {
-        let mut expr = self.unary();
-
-        loop {
-            let op = if self.match_token(TokenType::Star) {
-                BinaryOp::Multiply
-            } else if self.match_token(TokenType::Slash) {
-                BinaryOp::Divide
-            } else if self.match_token(TokenType::Percent) {
-                BinaryOp::Modulo
-            } else {
-                break;
-            };
-
-            let right = self.unary();
-            expr = Expression::Binary {
-                left: Box::new(expr),
-                operator: op,
-                right: Box::new(right),
-            };
+    fn binary_operator(&mut self, min_level: usize) -> Option<(usize, BinaryOp)> {
+        let token_type = self.peek().token_type;
+        for (level, operators) in BINARY_OPERATORS.iter().enumerate().skip(min_level) {
+            if let Some((_, operator)) = operators.iter().find(|(t, _)| *t == token_type) {
+                self.advance();
+                return Some((level, operator.clone()));
+            }
         }
-
-        expr
+        None
     }
 
-    fn unary(&mut self) -> Expression {
+    fn unary(&mut self) -> Result<Expression, Error> {
         let mut operators = Vec::new();
-        while self.match_token(TokenType::Bang) || self.match_token(TokenType::Minus) {
-            operators.push(if self.previous().token_type == TokenType::Bang {
+        loop {
+            let op = if self.match_token(TokenType::Bang) {
                 UnaryOp::Not
-            } else {
+            } else if self.match_token(TokenType::Minus) {
                 UnaryOp::Negate
-            });
+            } else if self.match_token(TokenType::Tilde) {
+                UnaryOp::BitNot
+            } else {
+                break;
+            };
+            operators.push(op);
         }
 
-        let mut expr = self.postfix_expression();
+        let mut expr = self.postfix_expression()?;
 
         // Apply unary operators in reverse order
         for op in operators.into_iter().rev() {
@@ -443,11 +446,11 @@ impl Parser {
             };
         }
 
-        expr
+        Ok(expr)
     }
 
-    fn postfix_expression(&mut self) -> Expression {
-        let mut expr = self.primary();
+    fn postfix_expression(&mut self) -> Result<Expression, Error> {
+        let mut expr = self.primary()?;
 
         while self.match_token_any_no_advance(&[
             TokenType::LeftParen,
@@ -455,21 +458,23 @@ impl Parser {
             TokenType::LeftBracket,
         ]) {
             if self.match_token(TokenType::LeftParen) {
-                let arguments = self.argument_list();
-                self.consume(TokenType::RightParen, "Expected ')' after arguments");
+                let start = self.previous_line();
+                let arguments = self.argument_list()?;
+                self.consume(TokenType::RightParen, "Expected ')' after arguments")?;
                 expr = Expression::Call {
                     callee: Box::new(expr),
                     arguments,
+                    span: self.span_from(start),
                 };
             } else if self.match_token(TokenType::Dot) {
-                let token = self.advance();
+                let token = self.consume(TokenType::Identifier, "Expected identifier after '.'")?;
                 expr = Expression::DotAccess {
                     object: Box::new(expr),
                     identifier: Box::new(self.identifier(token)),
                 };
             } else if self.match_token(TokenType::LeftBracket) {
-                let index = Box::new(self.expression());
-                self.consume(TokenType::RightBracket, "Expected ']' after index");
+                let index = Box::new(self.expression()?);
+                self.consume(TokenType::RightBracket, "Expected ']' after index")?;
                 expr = Expression::IndexAccess {
                     object: Box::new(expr),
                     index,
@@ -477,10 +482,10 @@ impl Parser {
             }
         }
 
-        expr
+        Ok(expr)
     }
 
-    fn primary(&mut self) -> Expression {
+    fn primary(&mut self) -> Result<Expression, Error> {
         if self.match_token_sequence_no_advance(&[TokenType::Identifier, TokenType::LeftBrace]) {
             return self.object_construction();
         } else if self.match_token(TokenType::LeftBrace) {
@@ -491,45 +496,57 @@ impl Parser {
             return self.group();
         }
 
+        if self.is_at_end() {
+            return Err(self.error("Expected expression, but found end of input"));
+        }
+
         let token = self.advance();
         match token.token_type {
-            TokenType::Identifier => self.identifier(token),
-            TokenType::StringLiteral => {
-                Expression::StringLiteral(token.lexeme[1..token.lexeme.len() - 1].to_string())
+            TokenType::Identifier => Ok(self.identifier(token)),
+            TokenType::StringLiteral => Ok(Expression::StringLiteral(intern(
+                &token.lexeme[1..token.lexeme.len() - 1],
+            ))),
+            TokenType::NumberLiteral => self.number_literal(&token),
+            TokenType::True => Ok(Expression::BoolLiteral(true)),
+            TokenType::False => Ok(Expression::BoolLiteral(false)),
+            TokenType::Null => Ok(Expression::Null),
+            TokenType::Invalid if token.lexeme.starts_with('"') => {
+                Err(self.error_at(&token, "Unterminated string literal"))
             }
-            TokenType::NumberLiteral => Expression::NumberLiteral(token.lexeme),
-            TokenType::True => Expression::BoolLiteral(true),
-            TokenType::False => Expression::BoolLiteral(false),
-            TokenType::Null => Expression::Null,
-            _ => panic!("Expected expression, but found {:?}", token),
+            _ => Err(self.error_at(
+                &token,
+                &format!("Expected expression, but found '{}'", token.lexeme),
+            )),
         }
     }
 
     fn identifier(&mut self, token: Token) -> Expression {
         if self.peek().token_type == TokenType::LeftParen {
-            return Expression::CallableIdentifier(token.lexeme);
+            return Expression::CallableIdentifier(intern(&token.lexeme));
         }
-        Expression::Identifier(token.lexeme)
+        Expression::Identifier(intern(&token.lexeme))
     }
 
-    fn group(&mut self) -> Expression {
-        let expr = self.expression();
-        self.consume(TokenType::RightParen, "Expected ')' after expression");
-        expr
+    fn group(&mut self) -> Result<Expression, Error> {
+        let expr = self.expression()?;
+        self.consume(TokenType::RightParen, "Expected ')' after expression")?;
+        Ok(expr)
     }
 
-    fn object_construction(&mut self) -> Expression {
-        let type_name = Some(self.type_identifier());
+    fn object_construction(&mut self) -> Result<Expression, Error> {
+        let type_name = Some(self.type_identifier()?);
 
-        self.consume(TokenType::LeftBrace, "Expected '{' after object type");
+        let start = self.line();
+        self.consume(TokenType::LeftBrace, "Expected '{' after object type")?;
 
-        let mut fields = HashMap::new();
+        let mut fields = Vec::new();
         while !self.check(TokenType::RightBrace) {
-            let name = self.consume_identifier("Expected field name");
+            let field_start = self.line();
+            let name = self.consume_symbol("Expected field name")?;
 
-            self.consume(TokenType::Equal, "Expected '=' after field name");
-            let value = self.expression();
-            fields.insert(name, value);
+            self.consume(TokenType::Equal, "Expected '=' after field name")?;
+            let value = self.expression()?;
+            insert_field(&mut fields, name, self.spanned(value, field_start));
 
             // Allow optional comma, including trailing comma
             if !self.match_token(TokenType::Comma) {
@@ -543,19 +560,25 @@ impl Parser {
             }
         }
 
-        self.consume(TokenType::RightBrace, "Expected '}' after object fields");
+        self.consume(TokenType::RightBrace, "Expected '}' after object fields")?;
 
-        Expression::ObjectConstruction { type_name, fields }
+        Ok(Expression::ObjectConstruction {
+            type_name,
+            fields,
+            span: self.span_from(start),
+        })
     }
 
-    fn anonymous_object_construction(&mut self) -> Expression {
-        let mut fields = HashMap::new();
+    fn anonymous_object_construction(&mut self) -> Result<Expression, Error> {
+        let start = self.previous_line();
+        let mut fields = Vec::new();
         while !self.check(TokenType::RightBrace) {
-            let name = self.consume_identifier("Expected field name");
+            let field_start = self.line();
+            let name = self.consume_symbol("Expected field name")?;
 
-            self.consume(TokenType::Equal, "Expected '=' after field name");
-            let value = self.expression();
-            fields.insert(name, value);
+            self.consume(TokenType::Equal, "Expected '=' after field name")?;
+            let value = self.expression()?;
+            insert_field(&mut fields, name, self.spanned(value, field_start));
 
             // Allow optional comma, including trailing comma
             if !self.match_token(TokenType::Comma) {
@@ -568,20 +591,24 @@ impl Parser {
             }
         }
 
-        self.consume(TokenType::RightBrace, "Expected '}' after object fields");
+        self.consume(TokenType::RightBrace, "Expected '}' after object fields")?;
 
-        Expression::ObjectConstruction {
+        Ok(Expression::ObjectConstruction {
             type_name: None,
             fields,
-        }
+            span: self.span_from(start),
+        })
     }
 
-    fn array_construction(&mut self) -> Expression {
+    fn array_construction(&mut self) -> Result<Expression, Error> {
+        let start = self.previous_line();
         let mut elements = Vec::new();
 
         if !self.check(TokenType::RightBracket) {
             loop {
-                elements.push(self.expression());
+                let element_start = self.line();
+                let element = self.expression()?;
+                elements.push(self.spanned(element, element_start));
 
                 // Allow optional comma, including trailing comma
                 if !self.match_token(TokenType::Comma) {
@@ -595,29 +622,83 @@ impl Parser {
             }
         }
 
-        self.consume(TokenType::RightBracket, "Expected ']' after array elements");
+        self.consume(TokenType::RightBracket, "Expected ']' after array elements")?;
 
-        Expression::ArrayConstruction { elements }
+        Ok(Expression::ArrayConstruction {
+            elements,
+            span: self.span_from(start),
+        })
     }
 
-    fn argument_list(&mut self) -> Vec<Expression> {
+    fn argument_list(&mut self) -> Result<Vec<Spanned<Expression>>, Error> {
         let mut arguments = Vec::new();
 
         if self.check(TokenType::RightParen) {
-            return arguments;
+            return Ok(arguments);
         }
 
         loop {
-            arguments.push(self.expression());
+            let start = self.line();
+            let argument = self.expression()?;
+            arguments.push(self.spanned(argument, start));
             if !self.match_token(TokenType::Comma) {
                 break;
             }
         }
 
-        arguments
+        Ok(arguments)
     }
 
     // Helper methods
+    fn line(&self) -> usize {
+        self.tokens[self.current].line
+    }
+
+    fn previous_line(&self) -> usize {
+        self.tokens[self.current - 1].line
+    }
+
+    // From `start` to the line of the last consumed token.
+    fn span_from(&self, start: usize) -> Span {
+        Span {
+            start,
+            end: self.previous_line(),
+        }
+    }
+
+    fn spanned<T>(&self, node: T, start: usize) -> Spanned<T> {
+        Spanned {
+            node,
+            span: self.span_from(start),
+        }
+    }
+
+    fn enter(&mut self) -> Result<(), Error> {
+        self.depth += 1;
+        if self.depth > MAX_NESTING_DEPTH {
+            return Err(self.error("Code is nested too deeply"));
+        }
+        Ok(())
+    }
+
+    fn leave(&mut self) {
+        self.depth -= 1;
+    }
+
+    fn error(&self, message: &str) -> Error {
+        self.error_at(&self.peek(), message)
+    }
+
+    fn error_at(&self, token: &Token, message: &str) -> Error {
+        Error::new(
+            ErrorKind::Syntax,
+            format!(
+                "{} at line {}, column {}",
+                message, token.line, token.column
+            ),
+        )
+    }
+
     fn match_token(&mut self, token_type: TokenType) -> bool {
         if self.check(token_type) {
             self.advance();
@@ -649,17 +730,71 @@ impl Parser {
         false
     }
 
-    fn consume(&mut self, token_type: TokenType, message: &str) -> Token {
+    fn consume(&mut self, token_type: TokenType, message: &str) -> Result<Token, Error> {
         if self.check(token_type) {
-            self.advance()
+            Ok(self.advance())
         } else {
-            panic!("{}, {:?}", message, self.peek());
+            let token = self.peek();
+            let found = match token.token_type {
+                TokenType::Eof => "end of input".to_string(),
+                _ => format!("'{}'", token.lexeme),
+            };
+            Err(self.error(&format!("{}, but found {}", message, found)))
         }
     }
 
-    fn consume_identifier(&mut self, message: &str) -> String {
-        let token = self.consume(TokenType::Identifier, message);
-        token.lexeme
+    fn consume_identifier(&mut self, message: &str) -> Result<String, Error> {
+        let token = self.consume(TokenType::Identifier, message)?;
+        Ok(token.lexeme)
+    }
+
+    fn number_literal(&self, token: &Token) -> Result<Expression, Error> {
+        let lexeme = token.lexeme.as_str();
+        let malformed = || self.error_at(token, &format!("Malformed number literal '{}'", lexeme));
+        let (radix, digits) = match lexeme.get(..2) {
+            Some("0x" | "0X") => (16, &lexeme[2..]),
+            Some("0b" | "0B") => (2, &lexeme[2..]),
+            Some("0o" | "0O") => (8, &lexeme[2..]),
+            _ => (10, lexeme),
+        };
+
+        // Separators may only sit between two digits.
+        let chars: Vec<char> = digits.chars().collect();
+        let separated = |i: usize| {
+            i > 0
+                && chars[i - 1].is_digit(radix)
+                && chars.get(i + 1).is_some_and(|c| c.is_digit(radix))
+        };
+        if (0..chars.len()).any(|i| chars[i] == '_' && !separated(i)) {
+            return Err(malformed());
+        }
+        let digits: String = chars.iter().filter(|&&c| c != '_').collect();
+
+        if radix == 10 && digits.contains(['.', 'e', 'E']) {
+            return match digits.parse::<f64>() {
+                Ok(n) if n.is_finite() => Ok(Expression::NumberLiteral(n, lexeme.to_string())),
+                Ok(_) => {
+                    Err(self.error_at(token, &format!("Number literal {} is out of range", lexeme)))
+                }
+                Err(_) => Err(malformed()),
+            };
+        }
+
+        if digits.is_empty() || !digits.chars().all(|c| c.is_digit(radix)) {
+            return Err(malformed());
+        }
+        i64::from_str_radix(&digits, radix)
+            .map(|n| Expression::IntegerLiteral(n, lexeme.to_string()))
+            .map_err(|_| {
+                self.error_at(
+                    token,
+                    &format!("Integer literal {} is out of range", lexeme),
+                )
+            })
+    }
+
+    fn consume_symbol(&mut self, message: &str) -> Result<Symbol, Error> {
+        Ok(intern(&self.consume_identifier(message)?))
     }
 
     fn check(&self, token_type: TokenType) -> bool {
@@ -690,19 +825,44 @@ impl Parser {
     }
 }
 
+// A repeated field keeps its last value, as it did when fields were a map, but stays where
+// the name first appeared.
+fn insert_field(
+    fields: &mut Vec<(Symbol, Spanned<Expression>)>,
+    name: Symbol,
+    value: Spanned<Expression>,
+) {
+    match fields.iter_mut().find(|(field, _)| *field == name) {
+        Some(field) => field.1.node = value.node,
+        None => fields.push((name, value)),
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
     use crate::lexer::Lexer;
 
-    fn parse(input: &str) -> Vec<Statement> {
+    fn parse(input: &str) -> Vec<StatementKind> {
         let mut lexer = Lexer::new(input);
         let tokens = lexer.lex();
 
         let mut parser = Parser::new(tokens);
-        let ast = parser.parse();
+        let statements = parser.parse().unwrap();
+        statements
+            .into_iter()
+            .map(|statement| statement.node)
+            .collect()
+    }
+
+    // Every test program sits on one line, so everything parsed spans just that line.
+    const LINE_ONE: Span = Span { start: 1, end: 1 };
 
-        ast
+    fn on_line_one<T>(node: T) -> Spanned<T> {
+        Spanned {
+            node,
+            span: LINE_ONE,
+        }
     }
 
     #[test]
@@ -710,10 +870,10 @@ mod tests {
         let input = "var x = 42;";
         let statements = parse(input);
 
-        let expected = vec![Statement::Var {
-            name: "x".to_string(),
+        let expected = vec![StatementKind::Var {
+            name: "x".into(),
             type_annotation: None,
-            initializer: Box::new(Expression::NumberLiteral("42".to_string())),
+            initializer: Box::new(Expression::IntegerLiteral(42, "42".into())),
         }];
 
         assert_eq!(statements, expected);
@@ -724,10 +884,10 @@ mod tests {
         let input = "var x: number = 42;";
         let statements = parse(input);
 
-        let expected = vec![Statement::Var {
-            name: "x".to_string(),
+        let expected = vec![StatementKind::Var {
+            name: "x".into(),
             type_annotation: Some(vec!["number".to_string()]),
-            initializer: Box::new(Expression::NumberLiteral("42".to_string())),
+            initializer: Box::new(Expression::IntegerLiteral(42, "42".into())),
         }];
 
         assert_eq!(statements, expected);
@@ -738,19 +898,47 @@ mod tests {
         let input = "var point = Point { x = 1, y = 2 };";
         let statements = parse(input);
 
-        let expected = vec![Statement::Var {
-            name: "point".to_string(),
+        let expected = vec![StatementKind::Var {
+            name: "point".into(),
             type_annotation: None,
             initializer: Box::new(Expression::ObjectConstruction {
                 type_name: Some("Point".to_string()),
-                fields: HashMap::from([
-                    ("x".to_string(), Expression::NumberLiteral("1".to_string())),
-                    ("y".to_string(), Expression::NumberLiteral("2".to_string())),
-                ]),
+                fields: vec![
+                    (
+                        "x".into(),
+                        on_line_one(Expression::IntegerLiteral(1, "1".into())),
+                    ),
+                    (
+                        "y".into(),
+                        on_line_one(Expression::IntegerLiteral(2, "2".into())),
+                    ),
+                ],
+                span: LINE_ONE,
             }),
         }];
 
         assert_eq!(statements, expected);
+
+        let statements = parse("var point = Point { x = 1, y = 2, x = 3 };");
+        let StatementKind::Var { initializer, .. } = &statements[0] else {
+            panic!("Expected var declaration");
+        };
+        let Expression::ObjectConstruction { fields, .. } = &**initializer else {
+            panic!("Expected object construction");
+        };
+        assert_eq!(
+            fields,
+            &vec![
+                (
+                    "x".into(),
+                    on_line_one(Expression::IntegerLiteral(3, "3".into()))
+                ),
+                (
+                    "y".into(),
+                    on_line_one(Expression::IntegerLiteral(2, "2".into()))
+                ),
+            ]
+        );
     }
 
     #[test]
@@ -758,18 +946,19 @@ mod tests {
         let input = "object Point { x() { return 1; } }";
         let statements = parse(input);
 
-        let expected = vec![Statement::Object {
+        let expected = vec![StatementKind::Object {
             name: "Point".to_string(),
             type_annotation: None,
             methods: vec![MethodDeclaration {
                 signature: MethodSignature {
-                    name: "x".to_string(),
+                    name: "x".into(),
                     params: vec![],
                     return_type: None,
+                    span: LINE_ONE,
                 },
-                body: vec![Statement::Return(Some(Expression::NumberLiteral(
-                    "1".to_string(),
-                )))],
+                body: on_line_one(vec![on_line_one(StatementKind::Return(Some(
+                    Expression::IntegerLiteral(1, "1".into()),
+                )))]),
             }],
         }];
 
@@ -781,17 +970,17 @@ mod tests {
         let input = "if (x == 1) { var y = 2; }";
         let statements = parse(input);
 
-        let expected = vec![Statement::If {
+        let expected = vec![StatementKind::If {
             condition: Box::new(Expression::Binary {
-                left: Box::new(Expression::Identifier("x".to_string())),
+                left: Box::new(Expression::Identifier("x".into())),
                 operator: BinaryOp::Equal,
-                right: Box::new(Expression::NumberLiteral("1".to_string())),
+                right: Box::new(Expression::IntegerLiteral(1, "1".into())),
             }),
-            then_branch: vec![Statement::Var {
-                name: "y".to_string(),
+            then_branch: on_line_one(vec![on_line_one(StatementKind::Var {
+                name: "y".into(),
                 type_annotation: None,
-                initializer: Box::new(Expression::NumberLiteral("2".to_string())),
-            }],
+                initializer: Box::new(Expression::IntegerLiteral(2, "2".into())),
+            })]),
             else_branch: None,
         }];
 
@@ -803,23 +992,103 @@ mod tests {
         let input = "var result = (1 + 2) * 3;";
         let statements = parse(input);
 
-        let expected = vec![Statement::Var {
-            name: "result".to_string(),
+        let expected = vec![StatementKind::Var {
+            name: "result".into(),
             type_annotation: None,
             initializer: Box::new(Expression::Binary {
                 left: Box::new(Expression::Binary {
-                    left: Box::new(Expression::NumberLiteral("1".to_string())),
+                    left: Box::new(Expression::IntegerLiteral(1, "1".into())),
                     operator: BinaryOp::Add,
-                    right: Box::new(Expression::NumberLiteral("2".to_string())),
+                    right: Box::new(Expression::IntegerLiteral(2, "2".into())),
                 }),
                 operator: BinaryOp::Multiply,
-                right: Box::new(Expression::NumberLiteral("3".to_string())),
+                right: Box::new(Expression::IntegerLiteral(3, "3".into())),
             }),
         }];
 
         assert_eq!(statements, expected);
     }
 
+    #[test]
+    fn test_number_literals() {
+        let literals = |input: &str| -> Vec<Expression> {
+            parse(input)
+                .into_iter()
+                .map(|statement| match statement {
+                    StatementKind::Expression(expression) => expression,
+                    statement => panic!("Unexpected statement {:?}", statement),
+                })
+                .collect()
+        };
+
+        assert_eq!(
+            literals("42; 1_000_000; 0xff; 0XFF_FF; 0b1010; 0o17; 9223372036854775807;"),
+            vec![
+                Expression::IntegerLiteral(42, "42".into()),
+                Expression::IntegerLiteral(1_000_000, "1_000_000".into()),
+                Expression::IntegerLiteral(0xff, "0xff".into()),
+                Expression::IntegerLiteral(0xffff, "0XFF_FF".into()),
+                Expression::IntegerLiteral(0b1010, "0b1010".into()),
+                Expression::IntegerLiteral(0o17, "0o17".into()),
+                Expression::IntegerLiteral(i64::MAX, "9223372036854775807".into()),
+            ]
+        );
+        assert_eq!(
+            literals("1.5; 1e-6; 2.5E+3; 1_000.000_1;"),
+            vec![
+                Expression::NumberLiteral(1.5, "1.5".into()),
+                Expression::NumberLiteral(1e-6, "1e-6".into()),
+                Expression::NumberLiteral(2500.0, "2.5E+3".into()),
+                Expression::NumberLiteral(1000.0001, "1_000.000_1".into()),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_bitwise_precedence() {
+        let number = |n: i64| Box::new(Expression::IntegerLiteral(n, n.to_string()));
+        let binary = |left, operator, right| {
+            Box::new(Expression::Binary {
+                left,
+                operator,
+                right,
+            })
+        };
+
+        // a | b ^ c & d == e
+        let statements = parse("1 | 2 ^ 3 & 4 == 5;");
+        let expected = binary(
+            number(1),
+            BinaryOp::BitOr,
+            binary(
+                number(2),
+                BinaryOp::BitXor,
+                binary(
+                    number(3),
+                    BinaryOp::BitAnd,
+                    binary(number(4), BinaryOp::Equal, number(5)),
+                ),
+            ),
+        );
+        assert_eq!(statements, vec![StatementKind::Expression(*expected)]);
+
+        // Shifts bind tighter than comparisons but looser than addition.
+        let statements = parse("1 << 2 + 3 < ~4;");
+        let expected = binary(
+            binary(
+                number(1),
+                BinaryOp::ShiftLeft,
+                binary(number(2), BinaryOp::Add, number(3)),
+            ),
+            BinaryOp::Less,
+            Box::new(Expression::Unary {
+                operator: UnaryOp::BitNot,
+                operand: number(4),
+            }),
+        );
+        assert_eq!(statements, vec![StatementKind::Expression(*expected)]);
+    }
+
     #[test]
     fn test_game_program() {
         let input = r#"
@@ -889,7 +1158,7 @@ mod tests {
 
         // Verify RenderContext object
         match &statements[0] {
-            Statement::Object { name, methods, .. } => {
+            StatementKind::Object { name, methods, .. } => {
                 assert_eq!(name, "RenderContext");
                 assert_eq!(methods.len(), 2); // init and deinit methods
             }
@@ -898,7 +1167,7 @@ mod tests {
 
         // Verify Renderable trait
         match &statements[1] {
-            Statement::Trait {
+            StatementKind::Trait {
                 name,
                 method_signatures,
                 ..
@@ -911,7 +1180,7 @@ mod tests {
 
         // Verify Updatable trait
         match &statements[2] {
-            Statement::Trait {
+            StatementKind::Trait {
                 name,
                 method_signatures,
                 ..
@@ -924,7 +1193,7 @@ mod tests {
 
         // Verify Text object inherits both traits
         match &statements[3] {
-            Statement::Object {
+            StatementKind::Object {
                 name,
                 type_annotation,
                 methods,
@@ -938,4 +1207,64 @@ mod tests {
             _ => panic!("Expected Text object declaration"),
         }
     }
+
+    #[test]
+    fn test_syntax_errors() {
+        let parse_error = |input: &str| {
+            let tokens = Lexer::new(input).lex();
+            Parser::new(tokens).parse().unwrap_err()
+        };
+
+        let error = parse_error("var x = 1\nvar y = 2;");
+        assert_eq!(error.kind, ErrorKind::Syntax);
+        assert_eq!(
+            error.message,
+            "Expected ';' after variable declaration, but found 'var' at line 2, column 1"
+        );
+
+        assert_eq!(
+            parse_error("if (x) {").message,
+            "Expected '}' after block, but found end of input at line 1, column 9"
+        );
+        assert_eq!(
+            parse_error("print(\"oops);").message,
+            "Unterminated string literal at line 1, column 7"
+        );
+        assert_eq!(
+            parse_error("try { }").message,
+            "Expected 'catch' or 'finally' after try block at line 1, column 8"
+        );
+        assert_eq!(
+            parse_error("0x;").message,
+            "Malformed number literal '0x' at line 1, column 1"
+        );
+        assert_eq!(
+            parse_error("1__0;").message,
+            "Malformed number literal '1__0' at line 1, column 1"
+        );
+        assert_eq!(
+            parse_error("1.5_;").message,
+            "Malformed number literal '1.5_' at line 1, column 1"
+        );
+        assert_eq!(
+            parse_error("0b102;").message,
+            "Malformed number literal '0b102' at line 1, column 1"
+        );
+        assert_eq!(
+            parse_error("1e;").message,
+            "Malformed number literal '1e' at line 1, column 1"
+        );
+        assert_eq!(
+            parse_error("99999999999999999999;").message,
+            "Integer literal 99999999999999999999 is out of range at line 1, column 1"
+        );
+        assert_eq!(
+            parse_error("1e999;").message,
+            "Number literal 1e999 is out of range at line 1, column 1"
+        );
+        assert_eq!(
+            parse_error(&"[".repeat(1000)).message,
+            "Code is nested too deeply at line 1, column 129"
+        );
+    }
 }
@@ -15,6 +15,10 @@ pub enum TokenType {
     Break,
     Continue,
     Return,
+    Try,
+    Catch,
+    Finally,
+    Throw,
 
     // Identifiers and literals
     Identifier,
@@ -43,6 +47,14 @@ pub enum TokenType {
     Or,   // ||
     Bang, // !
 
+    // Bitwise operators
+    Ampersand,      // &
+    Pipe,           // |
+    Caret,          // ^
+    Tilde,          // ~
+    LessLess,       // <<
+    GreaterGreater, // >>
+
     // Delimiters
     Dot,          // .
     Comma,        // ,
@@ -78,3 +90,10 @@ impl Token {
         }
     }
 }
+
+/// A `//` comment; the parser never sees these, but the formatter puts them back.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Comment {
+    pub text: String,
+    pub line: usize,
+}